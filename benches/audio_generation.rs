@@ -18,8 +18,10 @@ fn bench_parameters(c: &mut Criterion, label: &str, parameters: &DataParameters)
 }
 
 pub fn bench(c: &mut Criterion) {
-    let octave_parameters = OctaveParameters::new(0.5, 0.3, 90., 10_000.);
-    let wave_forms = WaveForms::new().load_dir_and_add("assets/custom_oscillators");
+    let octave_parameters = OctaveParameters::new(0.5, 0.3, 90., 10_000.).unwrap();
+    let wave_forms = WaveForms::new()
+        .load_dir_and_add("assets/custom_oscillators")
+        .unwrap();
     let single_note_parameters = DataParameters::new(
         44100,
         (50., 2000.),
@@ -28,7 +30,8 @@ pub fn bench(c: &mut Criterion) {
         octave_parameters,
         wave_forms.clone(),
         256,
-    );
+    )
+    .unwrap();
     let large_chord_parameters = DataParameters::new(
         44100,
         (50., 2000.),
@@ -37,95 +40,120 @@ pub fn bench(c: &mut Criterion) {
         octave_parameters,
         wave_forms,
         256,
-    );
+    )
+    .unwrap();
     let parameters = [
         ("empty", single_note_parameters.clone()),
         ("empty_chord", large_chord_parameters.clone()),
         (
             "sine",
-            single_note_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Sine,
-                1.,
-                (0.5, 0.7),
-            ),
+            single_note_parameters
+                .clone()
+                .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.7))
+                .unwrap(),
         ),
         (
             "sine_chord",
-            large_chord_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Sine,
-                1.,
-                (0.5, 0.7),
-            ),
+            large_chord_parameters
+                .clone()
+                .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.7))
+                .unwrap(),
         ),
         (
             "sine_dist",
             single_note_parameters
                 .clone()
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.7))
-                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.),
+                .unwrap()
+                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.)
+                .unwrap(),
         ),
         (
             "sine_chord_dist",
             large_chord_parameters
                 .clone()
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.7))
-                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.),
+                .unwrap()
+                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.)
+                .unwrap(),
         ),
         (
             "all_osc",
             single_note_parameters
                 .clone()
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Saw, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Triangle, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(
                     OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
                     1.,
                     (0.1, 0.2),
                 )
-                .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2)),
+                .unwrap()
+                .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2))
+                .unwrap(),
         ),
         (
             "all_osc_chord",
             large_chord_parameters
                 .clone()
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Saw, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Triangle, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(
                     OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
                     1.,
                     (0.1, 0.2),
                 )
-                .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2)),
+                .unwrap()
+                .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2))
+                .unwrap(),
         ),
         (
             "all_osc_dist",
             single_note_parameters
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Saw, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Triangle, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(
                     OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
                     1.,
                     (0.1, 0.2),
                 )
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2))
-                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.),
+                .unwrap()
+                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.)
+                .unwrap(),
         ),
         (
             "all_osc_chord_dist",
             large_chord_parameters
                 .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Saw, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Triangle, 1., (0.1, 0.2))
+                .unwrap()
                 .with_oscillator(
                     OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
                     1.,
                     (0.1, 0.2),
                 )
+                .unwrap()
                 .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.1, 0.2))
-                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.),
+                .unwrap()
+                .with_effect(EffectTypeDistribution::distortion((0.2, 20.)), 1.)
+                .unwrap(),
         ),
     ];
     for (label, parameters) in parameters.iter() {
@@ -134,8 +162,10 @@ pub fn bench(c: &mut Criterion) {
 }
 
 pub fn oscillators(c: &mut Criterion) {
-    let octave_parameters = OctaveParameters::new(0.5, 0.3, 90., 10_000.);
-    let wave_forms = WaveForms::new().load_dir_and_add("assets/custom_oscillators");
+    let octave_parameters = OctaveParameters::new(0.5, 0.3, 90., 10_000.).unwrap();
+    let wave_forms = WaveForms::new()
+        .load_dir_and_add("assets/custom_oscillators")
+        .unwrap();
     let base_parameters = DataParameters::new(
         44100,
         (50., 2000.),
@@ -144,44 +174,47 @@ pub fn oscillators(c: &mut Criterion) {
         octave_parameters,
         wave_forms,
         256,
-    );
+    )
+    .unwrap();
 
     let parameters = [
         (
             "osc_sine",
-            base_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Sine,
-                1.,
-                (0.5, 0.7),
-            ),
+            base_parameters
+                .clone()
+                .with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.7))
+                .unwrap(),
         ),
         (
             "osc_saw",
-            base_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Saw,
-                1.,
-                (0.5, 0.7),
-            ),
+            base_parameters
+                .clone()
+                .with_oscillator(OscillatorTypeDistribution::Saw, 1., (0.5, 0.7))
+                .unwrap(),
         ),
         (
             "osc_triangle",
-            base_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Triangle,
-                1.,
-                (0.5, 0.7),
-            ),
+            base_parameters
+                .clone()
+                .with_oscillator(OscillatorTypeDistribution::Triangle, 1., (0.5, 0.7))
+                .unwrap(),
         ),
         (
             "osc_pulse",
-            base_parameters.clone().with_oscillator(
-                OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
-                1.,
-                (0.5, 0.7),
-            ),
+            base_parameters
+                .clone()
+                .with_oscillator(
+                    OscillatorTypeDistribution::Pulse(UniformF::new(0.1, 0.9)),
+                    1.,
+                    (0.5, 0.7),
+                )
+                .unwrap(),
         ),
         (
             "osc_noise",
-            base_parameters.with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.5, 0.7)),
+            base_parameters
+                .with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.5, 0.7))
+                .unwrap(),
         ),
     ];
 
@@ -99,9 +99,9 @@ impl Audio {
         let mut module = module.create_instance();
         let mut samples = Vec::with_capacity(num_samples as usize);
         for sample_num in 0..num_samples {
-            let sample = module.next(sample_num as u64);
+            let sample = module.next(sample_num);
             if sample.abs() > 1. {
-                return Err(AudioGenerationError::Clipping(sample_num as u64));
+                return Err(AudioGenerationError::Clipping(sample_num));
             }
             samples.push(sample)
         }
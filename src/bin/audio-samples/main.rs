@@ -0,0 +1,304 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use audio_samples::{
+    data::LABELS_FILE_NAME, melody::MelodyParameters, midi::MidiFile, parameters::DataParameters,
+    wave_form_extraction, Audio,
+};
+use clap::{Args, Parser, Subcommand};
+
+/// Generates synthetic audio datasets from a config file.
+///
+/// Configs are JSON or TOML files, chosen by extension, that deserialize into `DataConfig`. Wave forms are
+/// referenced by paths relative to the config file. MIDI files with the notes of each data point are only written
+/// when `--midi` is given.
+#[derive(Parser)]
+#[command(name = "audio-samples")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a dataset of audio files with a labels file.
+    Generate {
+        #[command(flatten)]
+        config: ConfigArgs,
+        /// Directory the audio and labels are written to. Created if it does not exist.
+        output: PathBuf,
+        /// Number of data points.
+        #[arg(short = 'n', long)]
+        size: u64,
+        /// Index of the first data point, for extending an existing dataset.
+        #[arg(long, default_value_t = 0)]
+        start: u64,
+        /// Prefix of the file names.
+        #[arg(long, default_value = "synth")]
+        name: String,
+        /// Also writes a MIDI file with the notes of each data point.
+        #[arg(long)]
+        midi: bool,
+    },
+    /// Generates a dataset of melodic clips with note-level labels.
+    Melody {
+        #[command(flatten)]
+        config: ConfigArgs,
+        /// JSON or TOML file describing the `MelodyParameters`.
+        melody: PathBuf,
+        /// Directory the audio and labels are written to. Created if it does not exist.
+        output: PathBuf,
+        /// Number of clips.
+        #[arg(short = 'n', long)]
+        size: u64,
+        /// Index of the first clip, for extending an existing dataset.
+        #[arg(long, default_value_t = 0)]
+        start: u64,
+        /// Prefix of the file names.
+        #[arg(long, default_value = "melody")]
+        name: String,
+    },
+    /// Prints the sampled parameters and label of a single data point.
+    Inspect {
+        #[command(flatten)]
+        config: ConfigArgs,
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+    },
+    /// Renders a single data point to a WAV file.
+    RenderOne {
+        #[command(flatten)]
+        config: ConfigArgs,
+        output: PathBuf,
+        #[arg(short, long, default_value_t = 0)]
+        index: u64,
+        /// Also writes the notes to a MIDI file next to the audio.
+        #[arg(long)]
+        midi: bool,
+    },
+    /// Renders every MIDI file in a directory with timbres sampled from the config.
+    RenderMidi {
+        #[command(flatten)]
+        config: ConfigArgs,
+        /// Directory containing `.mid` or `.midi` files.
+        input: PathBuf,
+        /// Directory the audio and labels are written to. Created if it does not exist.
+        output: PathBuf,
+    },
+    /// Extracts single cycle wave forms from recordings, for use as custom oscillator wave forms.
+    ExtractWaveForms {
+        /// A WAV recording or a directory of them.
+        input: PathBuf,
+        /// Directory the wave forms are written to, with the file names of the recordings. Created if it does not
+        /// exist.
+        output: PathBuf,
+        /// Number of periods in each wave form.
+        #[arg(short, long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        periods: u64,
+    },
+}
+
+#[derive(Args)]
+struct ConfigArgs {
+    /// JSON or TOML file describing the `DataConfig`.
+    config: PathBuf,
+    /// Overrides the seed of the config.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+impl ConfigArgs {
+    /// Loads the config, rejecting it with every violation if it is invalid.
+    fn load(&self) -> Result<DataParameters> {
+        let parameters = DataParameters::load_config(&self.config)?;
+        Ok(match self.seed {
+            Some(seed) => parameters.with_seed_offset(seed),
+            None => parameters,
+        })
+    }
+}
+
+fn write_labels<T: serde::Serialize>(output: &Path, labels: &T) -> Result<()> {
+    let label_file =
+        File::create(output.join(LABELS_FILE_NAME)).context("Could not create labels file.")?;
+    serde_json::to_writer_pretty(label_file, labels)?;
+    Ok(())
+}
+
+fn generate(
+    parameters: &DataParameters,
+    output: &Path,
+    indices: impl Iterator<Item = u64>,
+    name: &str,
+    midi: bool,
+) -> Result<()> {
+    fs::create_dir_all(output).context("Could not create output directory.")?;
+    let mut labels = Vec::new();
+    for index in indices {
+        let data_point = parameters.generate(index).generate()?;
+        let data_point_name = format!("{name}_{index}");
+        data_point
+            .audio()
+            .to_wav(output.join(format!("{data_point_name}.wav")))
+            .context("Failed to write sample.")?;
+        if midi {
+            data_point
+                .midi_file()
+                .save(output.join(format!("{data_point_name}.mid")))
+                .context("Failed to write MIDI file.")?;
+        }
+        labels.push((data_point_name, data_point.label()));
+    }
+    write_labels(output, &labels)
+}
+
+fn generate_melodies(
+    parameters: &DataParameters,
+    melody_parameters: &MelodyParameters,
+    output: &Path,
+    indices: impl Iterator<Item = u64>,
+    name: &str,
+) -> Result<()> {
+    fs::create_dir_all(output).context("Could not create output directory.")?;
+    let mut labels = Vec::new();
+    for index in indices {
+        let melody = melody_parameters.generate(parameters, index).generate();
+        let melody_name = format!("{name}_{index}");
+        melody
+            .audio()
+            .to_wav(output.join(format!("{melody_name}.wav")))
+            .context("Failed to write sample.")?;
+        labels.push((melody_name, melody.label()));
+    }
+    write_labels(output, &labels)
+}
+
+fn render_midi(parameters: &DataParameters, input: &Path, output: &Path) -> Result<()> {
+    let mut midi_paths: Vec<_> = fs::read_dir(input)
+        .context("Could not read MIDI directory.")?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    midi_paths.retain(|path| {
+        path.extension()
+            .map(|extension| extension == "mid" || extension == "midi")
+            .unwrap_or(false)
+    });
+    midi_paths.sort();
+
+    fs::create_dir_all(output).context("Could not create output directory.")?;
+    let mut labels = Vec::with_capacity(midi_paths.len());
+    for (index, midi_path) in midi_paths.iter().enumerate() {
+        let midi_file = MidiFile::load(midi_path)
+            .with_context(|| format!("Could not load {}.", midi_path.display()))?;
+        let sequence = midi_file.render(parameters, index as u64)?.generate();
+
+        let name = midi_path
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        sequence
+            .audio()
+            .to_wav(output.join(format!("{name}.wav")))
+            .context("Failed to write sample.")?;
+        labels.push((name, sequence.label()));
+    }
+    write_labels(output, &labels)
+}
+
+fn extract_wave_forms(input: &Path, output: &Path, num_periods: usize) -> Result<()> {
+    let recordings: Vec<PathBuf> = if input.is_dir() {
+        let mut recordings: Vec<_> = fs::read_dir(input)
+            .context("Could not read input directory.")?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        recordings.retain(|path| path.extension().map(|ext| ext == "wav").unwrap_or(false));
+        recordings.sort();
+        recordings
+    } else {
+        vec![input.to_owned()]
+    };
+
+    fs::create_dir_all(output).context("Could not create output directory.")?;
+    for recording in recordings {
+        let audio = Audio::from_wav(&recording)
+            .with_context(|| format!("Failed to load '{}'.", recording.display()))?;
+        match wave_form_extraction::extract_wave_form(&audio, num_periods) {
+            Ok(wave_form) => {
+                let file_name = recording
+                    .file_name()
+                    .context("Recording path has no file name.")?;
+                let wave_form_path = output.join(file_name);
+                Audio::from_samples(wave_form, audio.sample_rate).to_wav(&wave_form_path)?;
+                println!("Written {}", wave_form_path.display());
+            }
+            Err(error) => println!("Skipped {}: {error}", recording.display()),
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Generate {
+            config,
+            output,
+            size,
+            start,
+            name,
+            midi,
+        } => generate(&config.load()?, &output, start..start + size, &name, midi),
+        Command::Melody {
+            config,
+            melody,
+            output,
+            size,
+            start,
+            name,
+        } => generate_melodies(
+            &config.load()?,
+            &MelodyParameters::load(melody)?,
+            &output,
+            start..start + size,
+            &name,
+        ),
+        Command::Inspect { config, index } => {
+            let data_point = config.load()?.generate(index).generate()?;
+            println!("{:#?}", data_point.parameters());
+            println!("{}", serde_json::to_string_pretty(&data_point.label())?);
+            Ok(())
+        }
+        Command::RenderOne {
+            config,
+            output,
+            index,
+            midi,
+        } => {
+            let data_point = config.load()?.generate(index).generate()?;
+            data_point
+                .audio()
+                .to_wav(&output)
+                .context("Failed to write sample.")?;
+            if midi {
+                data_point
+                    .midi_file()
+                    .save(output.with_extension("mid"))
+                    .context("Failed to write MIDI file.")?;
+            }
+            Ok(())
+        }
+        Command::RenderMidi {
+            config,
+            input,
+            output,
+        } => render_midi(&config.load()?, &input, &output),
+        Command::ExtractWaveForms {
+            input,
+            output,
+            periods,
+        } => extract_wave_forms(&input, &output, periods as usize),
+    }
+}
@@ -90,8 +90,25 @@ fn main() -> Result<()> {
                     audio_samples::frequency_to_note_number(base_frequency),
                 )),
                 chord_type: 0,
+                chord_name: Some(audio_samples::CHORD_TYPES[0].0.to_owned()),
+                chord_intervals: Some(Vec::new()),
+                chord_symbol: None,
+                key: None,
+                roman_numeral: None,
+                tuning: None,
+                a4_frequency: None,
+                inversion: None,
+                bass_note: Some(note_number % 12),
+                midi_notes: Some(vec![note_number]),
+                chord_multi_hot: None,
+                note_multi_hot: None,
+                intended_note: Some(note_number),
+                detune: None,
                 num_samples: samples.len() as u64,
-            };
+                vibrato_depth: None,
+                unison: None,
+            }
+            .with_multi_hot_vectors();
 
             let data_point_name = format!("{}_{}_{}", run_name, note_number, sub_index);
 
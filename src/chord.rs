@@ -1,24 +1,215 @@
 use std::iter;
 
-#[derive(Debug, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ConfigError, tuning::Tuning};
+
+/// A chord defined by the intervals of its notes above the root in semitones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedChordType")]
 pub struct ChordType {
-    offsets: &'static [f32],
+    intervals: Vec<u32>,
+}
+
+/// Fields of a [`ChordType`] before its intervals are checked.
+#[derive(Deserialize)]
+struct SerializedChordType {
+    intervals: Vec<u32>,
+}
+
+impl TryFrom<SerializedChordType> for ChordType {
+    type Error = ConfigError;
+
+    fn try_from(chord_type: SerializedChordType) -> Result<Self, Self::Error> {
+        Self::new(chord_type.intervals)
+    }
 }
 
 impl ChordType {
-    pub const fn new(offsets: &'static [f32]) -> Self {
-        Self { offsets }
+    /// Fails unless the intervals are above the root and strictly ascending.
+    pub fn new<A>(intervals: A) -> Result<Self, ConfigError>
+    where
+        A: Into<Vec<u32>>,
+    {
+        let chord_type = Self::from_valid_intervals(intervals);
+        chord_type.validate()?;
+        Ok(chord_type)
+    }
+
+    /// For intervals that are known to be valid, such as the built-in chords.
+    pub(crate) fn from_valid_intervals<A>(intervals: A) -> Self
+    where
+        A: Into<Vec<u32>>,
+    {
+        Self {
+            intervals: intervals.into(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let ascending = self.intervals.first() != Some(&0)
+            && self.intervals.windows(2).all(|pair| pair[0] < pair[1]);
+        if ascending {
+            Ok(())
+        } else {
+            Err(ConfigError::InvalidIntervals(self.intervals.clone()))
+        }
+    }
+
+    /// Intervals above the root in semitones.
+    pub fn intervals(&self) -> &[u32] {
+        &self.intervals
     }
 
     pub fn num_notes(&self) -> usize {
-        self.offsets.len() + 1
+        self.intervals.len() + 1
     }
 
-    pub fn frequencies(&self, base_frequency: f32) -> impl Iterator<Item = f32> {
+    /// Frequencies of the notes of the chord, starting with the root.
+    pub fn frequencies<'a>(
+        &'a self,
+        base_frequency: f32,
+        tuning: &'a Tuning,
+    ) -> impl Iterator<Item = f32> + 'a {
         iter::once(base_frequency).chain(
-            self.offsets
+            self.intervals
                 .iter()
-                .map(move |&offset| base_frequency * offset),
+                .map(move |&interval| base_frequency * tuning.ratio(interval as i32)),
         )
     }
 }
+
+/// Named chord types that data points can be generated from.
+///
+/// Chords are referenced by their index in the registry, which is stable as chords can only be added.
+/// The default registry contains [`crate::CHORD_TYPES`] in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedChordRegistry")]
+pub struct ChordRegistry {
+    chords: Vec<(String, ChordType)>,
+}
+
+/// Fields of a [`ChordRegistry`] before the names are checked to be unique.
+#[derive(Deserialize)]
+struct SerializedChordRegistry {
+    chords: Vec<(String, ChordType)>,
+}
+
+impl TryFrom<SerializedChordRegistry> for ChordRegistry {
+    type Error = ConfigError;
+
+    fn try_from(registry: SerializedChordRegistry) -> Result<Self, Self::Error> {
+        registry
+            .chords
+            .into_iter()
+            .try_fold(Self::empty(), |registry, (name, chord_type)| {
+                registry.with_chord(name, chord_type)
+            })
+    }
+}
+
+impl ChordRegistry {
+    /// A registry with no chords.
+    pub fn empty() -> Self {
+        Self { chords: Vec::new() }
+    }
+
+    /// Adds a chord with the given name. Fails if the name is already taken.
+    pub fn with_chord<S>(mut self, name: S, chord_type: ChordType) -> Result<Self, ConfigError>
+    where
+        S: Into<String>,
+    {
+        let name = name.into();
+        if self.index(&name).is_some() {
+            return Err(ConfigError::DuplicateChord(name));
+        }
+        self.chords.push((name, chord_type));
+        Ok(self)
+    }
+
+    pub fn index(&self, name: &str) -> Option<u32> {
+        self.chords
+            .iter()
+            .position(|(chord_name, _)| chord_name == name)
+            .map(|index| index as u32)
+    }
+
+    /// Index of the first chord with the given intervals.
+    pub fn index_of(&self, chord_type: &ChordType) -> Option<u32> {
+        self.chords
+            .iter()
+            .position(|(_, other)| other == chord_type)
+            .map(|index| index as u32)
+    }
+
+    pub fn get(&self, index: u32) -> &ChordType {
+        &self.chords[index as usize].1
+    }
+
+    pub fn name(&self, index: u32) -> &str {
+        &self.chords[index as usize].0
+    }
+
+    pub fn len(&self) -> usize {
+        self.chords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chords.is_empty()
+    }
+}
+
+impl Default for ChordRegistry {
+    fn default() -> Self {
+        Self {
+            chords: crate::CHORD_TYPES
+                .iter()
+                .map(|&(name, intervals)| {
+                    (name.to_owned(), ChordType::from_valid_intervals(intervals))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChordRegistry;
+    use crate::tuning::Tuning;
+
+    #[test]
+    fn default_chords_keep_just_ratios() {
+        let registry = ChordRegistry::default();
+        let cases: [(&str, &[f32]); 4] = [
+            ("Major", &[1., 5. / 4., 3. / 2.]),
+            ("Minor 7th", &[1., 6. / 5., 3. / 2., 9. / 5.]),
+            ("Diminished", &[1., 6. / 5., std::f32::consts::SQRT_2]),
+            ("Dominant 9th", &[1., 5. / 4., 3. / 2., 9. / 5., 9. / 4.]),
+        ];
+        for (name, ratios) in cases {
+            let chord = registry.get(registry.index(name).unwrap());
+            let frequencies: Vec<f32> = chord.frequencies(100., &Tuning::JustIntonation).collect();
+            let expected: Vec<f32> = ratios.iter().map(|ratio| ratio * 100.).collect();
+            assert_eq!(frequencies, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn deserialized_chords_are_checked() {
+        let registry = |chords: &str| {
+            serde_json::from_str::<ChordRegistry>(&format!(r#"{{"chords": {chords}}}"#))
+        };
+        assert_eq!(
+            registry(r#"[["Power", {"intervals": [7]}], ["Sus2", {"intervals": [2, 7]}]]"#)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(
+            registry(r#"[["Power", {"intervals": [7]}], ["Power", {"intervals": [7, 12]}]]"#)
+                .is_err()
+        );
+        assert!(registry(r#"[["Unison", {"intervals": [0, 7]}]]"#).is_err());
+        assert!(registry(r#"[["Unsorted", {"intervals": [7, 4]}]]"#).is_err());
+    }
+}
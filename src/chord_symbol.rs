@@ -0,0 +1,197 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::chord::ChordType;
+
+/// Spelling of each pitch class when formatting chord symbols, with C as 0.
+const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+/// Chord quality suffixes and the intervals of the chords they stand for. The first suffix of each quality is
+/// used when formatting.
+const QUALITIES: &[(&[&str], &[u32])] = &[
+    (&["1"], &[]),
+    (&["5"], &[7]),
+    (&["", "maj", "M"], &[4, 7]),
+    (&["m", "min", "-"], &[3, 7]),
+    (&["dim", "o"], &[3, 6]),
+    (&["sus4", "sus"], &[5, 7]),
+    (&["maj7", "M7", "Δ7", "Δ"], &[4, 7, 11]),
+    (&["m7", "min7", "-7"], &[3, 7, 10]),
+    (&["7", "dom7"], &[4, 7, 10]),
+    (&["aug", "+"], &[4, 8]),
+    (&["sus2"], &[2, 7]),
+    (&["6", "maj6"], &[4, 7, 9]),
+    (&["m6", "min6"], &[3, 7, 9]),
+    (&["m7b5", "ø", "ø7"], &[3, 6, 10]),
+    (&["dim7", "o7"], &[3, 6, 9]),
+    (&["maj9", "M9"], &[4, 7, 11, 14]),
+    (&["m9", "min9"], &[3, 7, 10, 14]),
+    (&["9"], &[4, 7, 10, 14]),
+    (&["add9"], &[4, 7, 14]),
+    (&["11"], &[7, 10, 14, 17]),
+    (&["13"], &[4, 7, 10, 14, 21]),
+];
+
+#[derive(Debug)]
+pub enum ChordSymbolError {
+    InvalidRoot(String),
+    UnknownQuality(String),
+    InvalidBass(String),
+}
+
+impl Display for ChordSymbolError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidRoot(symbol) => write!(f, "'{symbol}' does not start with a note name."),
+            Self::UnknownQuality(quality) => write!(f, "Unknown chord quality '{quality}'."),
+            Self::InvalidBass(bass) => write!(f, "Invalid bass note '{bass}'."),
+        }
+    }
+}
+
+impl Error for ChordSymbolError {}
+
+/// Intervals of the chord quality with the given suffix, such as `m7` or `maj9`.
+pub fn quality_intervals(suffix: &str) -> Option<&'static [u32]> {
+    QUALITIES
+        .iter()
+        .find(|(suffixes, _)| suffixes.contains(&suffix))
+        .map(|&(_, intervals)| intervals)
+}
+
+/// Suffix of the chord quality with the given intervals, if it has one.
+pub fn quality_suffix(chord_type: &ChordType) -> Option<&'static str> {
+    QUALITIES
+        .iter()
+        .find(|(_, intervals)| *intervals == chord_type.intervals())
+        .map(|(suffixes, _)| suffixes[0])
+}
+
+pub fn pitch_class_name(pitch_class: u32) -> &'static str {
+    PITCH_CLASS_NAMES[pitch_class as usize % 12]
+}
+
+/// Parses a note name without octave, such as `C`, `F#` or `Bb`, into a pitch class. Returns the pitch class and
+/// the rest of the string.
+fn parse_pitch_class(text: &str) -> Option<(u32, &str)> {
+    let mut chars = text.chars();
+    let natural: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let after_accidentals = rest.trim_start_matches(['#', 'b']);
+    let alteration: i32 = rest[..rest.len() - after_accidentals.len()]
+        .chars()
+        .map(|accidental| if accidental == '#' { 1 } else { -1 })
+        .sum();
+    Some((
+        (natural + alteration).rem_euclid(12) as u32,
+        after_accidentals,
+    ))
+}
+
+/// A chord symbol such as `C#m7` or `Bbmaj9/D`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChordSymbol {
+    root: u32,
+    chord_type: ChordType,
+    bass: Option<u32>,
+}
+
+impl ChordSymbol {
+    /// Returns `None` if the chord type has no quality suffix. A bass note equal to the root is dropped.
+    pub fn new(root: u32, chord_type: ChordType, bass: Option<u32>) -> Option<Self> {
+        quality_suffix(&chord_type)?;
+        Some(Self {
+            root: root % 12,
+            chord_type,
+            bass: bass.map(|bass| bass % 12).filter(|&bass| bass != root % 12),
+        })
+    }
+
+    /// Pitch class of the root, with C as 0.
+    pub fn root(&self) -> u32 {
+        self.root
+    }
+
+    pub fn chord_type(&self) -> &ChordType {
+        &self.chord_type
+    }
+
+    /// Pitch class of the bass note if it differs from the root.
+    pub fn bass(&self) -> Option<u32> {
+        self.bass
+    }
+}
+
+impl FromStr for ChordSymbol {
+    type Err = ChordSymbolError;
+
+    fn from_str(symbol: &str) -> Result<Self, Self::Err> {
+        let symbol = symbol.trim();
+        let (root, rest) = parse_pitch_class(symbol)
+            .ok_or_else(|| ChordSymbolError::InvalidRoot(symbol.to_owned()))?;
+        let (quality, bass) = match rest.rsplit_once('/') {
+            Some((quality, bass)) => match parse_pitch_class(bass) {
+                Some((bass, "")) => (quality, Some(bass)),
+                _ => return Err(ChordSymbolError::InvalidBass(bass.to_owned())),
+            },
+            None => (rest, None),
+        };
+        let intervals = quality_intervals(quality)
+            .ok_or_else(|| ChordSymbolError::UnknownQuality(quality.to_owned()))?;
+        Ok(Self::new(root, ChordType::from_valid_intervals(intervals), bass).unwrap())
+    }
+}
+
+impl Display for ChordSymbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let suffix = quality_suffix(&self.chord_type).unwrap();
+        write!(f, "{}{suffix}", pitch_class_name(self.root))?;
+        if let Some(bass) = self.bass {
+            write!(f, "/{}", pitch_class_name(bass))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChordSymbol;
+    use crate::chord::ChordType;
+
+    #[test]
+    fn parse_and_format() {
+        let symbol = |root, intervals: &[u32], bass| {
+            ChordSymbol::new(root, ChordType::new(intervals).unwrap(), bass).unwrap()
+        };
+        let cases = [
+            ("C#m7", symbol(1, &[3, 7, 10], None), "C#m7"),
+            ("Bbmaj9/D", symbol(10, &[4, 7, 11, 14], Some(2)), "Bbmaj9/D"),
+            ("G", symbol(7, &[4, 7], None), "G"),
+            ("Fbdim7", symbol(4, &[3, 6, 9], None), "Edim7"),
+            ("Am/A", symbol(9, &[3, 7], None), "Am"),
+        ];
+        for (text, expected, formatted) in cases {
+            let parsed: ChordSymbol = text.parse().unwrap();
+            assert_eq!(parsed, expected, "{text}");
+            assert_eq!(parsed.to_string(), formatted, "{text}");
+        }
+        assert_eq!(symbol(9, &[3, 7], Some(9)).bass(), None);
+        assert!("H7".parse::<ChordSymbol>().is_err());
+        assert!("Cmaj13#11".parse::<ChordSymbol>().is_err());
+        assert!("C/X".parse::<ChordSymbol>().is_err());
+    }
+}
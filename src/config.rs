@@ -0,0 +1,259 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::scala::ScalaError;
+
+/// Where wave forms, wavetables and instruments come from. Paths in a config are relative to the config file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WaveFormSource {
+    /// A WAV file holding a single period.
+    File(PathBuf),
+    /// Every WAV file in a directory as a wave form, in order of file name.
+    Directory(PathBuf),
+    /// Periods extracted from a recording of a sustained note.
+    Extracted { path: PathBuf, num_periods: usize },
+    /// A directory of recorded notes played as a sampled instrument.
+    Instrument(PathBuf),
+    /// A wavetable morphing between wave forms loaded before it, by index.
+    Wavetable(Vec<usize>),
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A file could not be read or written.
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A config file could not be parsed or written.
+    Syntax {
+        path: PathBuf,
+        message: String,
+    },
+    /// A file referenced by a config does not hold usable audio.
+    InvalidAudio {
+        path: PathBuf,
+        message: String,
+    },
+    /// A wavetable needs at least two wave forms, all loaded before it.
+    InvalidWavetable {
+        indices: Vec<usize>,
+        num_wave_forms: usize,
+    },
+    /// An oscillator refers to a wave form, wavetable or instrument that is not loaded.
+    MissingBank {
+        kind: &'static str,
+        index: usize,
+        available: usize,
+    },
+    /// A custom tuning does not have twelve finite offsets.
+    InvalidTuning(Vec<f32>),
+    /// A Scala tuning cannot be used.
+    Scala(ScalaError),
+    /// A list that needs at least one entry is empty.
+    Empty(&'static str),
+    /// A chord index is not in the chord registry.
+    InvalidChord(u32),
+    NoChords,
+    /// No chord with the given name or quality suffix is registered.
+    UnknownChord(String),
+    /// A chord with the given name is already registered.
+    DuplicateChord(String),
+    /// Chord intervals are not above the root and strictly ascending.
+    InvalidIntervals(Vec<u32>),
+    /// No chord with the given intervals is registered.
+    UnregisteredChord(Vec<u32>),
+    /// A chord of a progression, given by its intervals, is not registered or not among the possible chords.
+    UnavailableChord(Vec<u32>),
+    /// None of the oscillators has a frequency, so no note can be generated.
+    NoPitchedOscillator,
+    /// Unison is set on an oscillator without a frequency.
+    UnpitchedUnison,
+    /// The maximum amplitudes of the oscillators add up to more than 1.
+    AmplitudeSum(f32),
+    /// A value that must be positive is zero.
+    Zero(&'static str),
+    /// A value lies outside the interval it must be in, such as a probability above 1.
+    OutOfRange {
+        name: &'static str,
+        value: f64,
+        interval: &'static str,
+    },
+    /// A range of values is empty or not within the values allowed for it.
+    InvalidRange {
+        name: &'static str,
+        range: (f64, f64),
+        requirement: &'static str,
+    },
+    /// A value does not have the shape it needs, such as a weight matrix of the wrong size.
+    Invalid {
+        name: &'static str,
+        requirement: &'static str,
+    },
+    /// Several constraints are violated at once.
+    Multiple(Vec<ConfigError>),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "Could not access '{}': {error}", path.display()),
+            Self::Syntax { path, message } => write!(f, "Invalid config '{}': {message}", path.display()),
+            Self::InvalidAudio { path, message } => {
+                write!(f, "Could not load '{}': {message}", path.display())
+            }
+            Self::InvalidWavetable {
+                indices,
+                num_wave_forms,
+            } => write!(
+                f,
+                "Invalid wavetable {indices:?}. A wavetable needs at least two of the {num_wave_forms} wave forms loaded before it."
+            ),
+            Self::MissingBank {
+                kind,
+                index,
+                available,
+            } => write!(f, "There is no {kind} with index {index}. Only {available} are loaded."),
+            Self::InvalidTuning(cents) => write!(
+                f,
+                "A custom tuning needs 12 finite offsets in cents. Offsets: {cents:?}"
+            ),
+            Self::Scala(error) => write!(f, "Invalid Scala tuning: {error}"),
+            Self::Empty(name) => write!(f, "No {name} provided."),
+            Self::InvalidChord(chord_type) => write!(f, "Invalid chord type {chord_type}."),
+            Self::NoChords => write!(f, "No chords provided."),
+            Self::UnknownChord(name) => write!(f, "No chord named '{name}' is registered."),
+            Self::DuplicateChord(name) => write!(f, "A chord named '{name}' already exists."),
+            Self::InvalidIntervals(intervals) => write!(
+                f,
+                "Chord intervals must be above the root and strictly ascending. Intervals: {intervals:?}"
+            ),
+            Self::UnregisteredChord(intervals) => {
+                write!(f, "No chord with the intervals {intervals:?} is registered.")
+            }
+            Self::UnavailableChord(intervals) => write!(
+                f,
+                "The chord with the intervals {intervals:?} is not registered or not among the possible chords."
+            ),
+            Self::NoPitchedOscillator => write!(f, "At least one oscillator must have a frequency."),
+            Self::UnpitchedUnison => write!(f, "Unison is only supported for oscillators with frequency."),
+            Self::AmplitudeSum(sum) => write!(
+                f,
+                "The sum of oscillator amplitudes must not exceed 1. Current: {sum}"
+            ),
+            Self::Zero(name) => write!(f, "{name} must be greater than 0."),
+            Self::OutOfRange {
+                name,
+                value,
+                interval,
+            } => write!(f, "{name} must be within {interval}. Value: {value}"),
+            Self::InvalidRange {
+                name,
+                range,
+                requirement,
+            } => write!(f, "Invalid {name} range {range:?}: {requirement}."),
+            Self::Invalid { name, requirement } => write!(f, "Invalid {name}: {requirement}."),
+            Self::Multiple(errors) => {
+                write!(f, "{} constraints are violated:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n- {error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+pub(crate) fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension == "toml")
+        .unwrap_or(false)
+}
+
+/// Reads a config file as TOML if the extension is `.toml` and as JSON otherwise.
+pub(crate) fn read<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let text = fs::read_to_string(path).map_err(|error| ConfigError::Io {
+        path: path.to_owned(),
+        error,
+    })?;
+    let syntax_error = |message: String| ConfigError::Syntax {
+        path: path.to_owned(),
+        message,
+    };
+    if is_toml(path) {
+        toml::from_str(&text).map_err(|error| syntax_error(error.to_string()))
+    } else {
+        serde_json::from_str(&text).map_err(|error| syntax_error(error.to_string()))
+    }
+}
+
+/// Collects every violated constraint of a config instead of stopping at the first.
+#[derive(Default)]
+pub(crate) struct Violations(Vec<ConfigError>);
+
+impl Violations {
+    /// Records the error if `valid` is false.
+    pub(crate) fn check(&mut self, valid: bool, error: impl FnOnce() -> ConfigError) {
+        if !valid {
+            self.0.push(error());
+        }
+    }
+
+    pub(crate) fn check_probability(&mut self, name: &'static str, value: f64, allow_zero: bool) {
+        let (valid, interval) = if allow_zero {
+            ((0. ..=1.).contains(&value), "[0;1]")
+        } else {
+            (0. < value && value <= 1., "(0;1]")
+        };
+        self.check(valid, || ConfigError::OutOfRange {
+            name,
+            value,
+            interval,
+        });
+    }
+
+    /// Records an error unless the range is finite, non-empty and within the inclusive `bounds`.
+    pub(crate) fn check_range(
+        &mut self,
+        name: &'static str,
+        range: (f32, f32),
+        bounds: (f32, f32),
+        requirement: &'static str,
+    ) {
+        let valid = range.0.is_finite()
+            && range.1.is_finite()
+            && bounds.0 <= range.0
+            && range.0 <= range.1
+            && range.1 <= bounds.1;
+        self.check(valid, || ConfigError::InvalidRange {
+            name,
+            range: (range.0 as f64, range.1 as f64),
+            requirement,
+        });
+    }
+
+    /// Records the violations of a nested part of the config.
+    pub(crate) fn add(&mut self, result: Result<(), ConfigError>) {
+        match result {
+            Ok(()) => {}
+            Err(ConfigError::Multiple(errors)) => self.0.extend(errors),
+            Err(error) => self.0.push(error),
+        }
+    }
+
+    pub(crate) fn into_result(mut self) -> Result<(), ConfigError> {
+        match self.0.len() {
+            0 => Ok(()),
+            1 => Err(self.0.pop().unwrap()),
+            _ => Err(ConfigError::Multiple(self.0)),
+        }
+    }
+}
@@ -1,14 +1,23 @@
 use std::path::Path;
 
 use anyhow::Context;
-use rand::{distributions, Rng, SeedableRng};
-use rand_pcg::Pcg64Mcg;
 use serde::{Deserialize, Serialize};
 
-use crate::{audio::AudioGenerationError, parameters::DataPointParameters, Audio};
+use crate::{
+    audio::AudioGenerationError,
+    chord_symbol::ChordSymbol,
+    frames::{FrameLabels, Framing},
+    midi::MidiFile,
+    parameters::DataPointParameters,
+    tuning::Tuning,
+    Audio,
+};
 
 pub const LABELS_FILE_NAME: &str = "_labels.json";
 
+/// MIDI note number of the lowest key on a piano, A0.
+const PIANO_LOWEST_NOTE: u32 = 21;
+
 #[derive(Clone)]
 pub struct DataPoint {
     pub audio: Audio,
@@ -16,19 +25,22 @@ pub struct DataPoint {
 }
 
 impl DataPoint {
-    fn generate_from_oscillators(parameters: &DataPointParameters) -> Vec<f32> {
+    pub(crate) fn generate_from_oscillators(parameters: &DataPointParameters) -> Vec<f32> {
         let mut samples = vec![0.; parameters.num_samples as usize];
 
-        let (_, chord_type) = crate::CHORD_TYPES[parameters.chord_type as usize];
-
-        let mut rng = Pcg64Mcg::seed_from_u64(parameters.frequency_walk_seed);
+        let pitch_factors = parameters.vibrato_factors();
 
-        for &frequency in parameters.frequencies.iter() {
-            for oscillator_params in parameters.oscillators.iter() {
+        for (note_index, &frequency) in parameters.frequencies.iter().enumerate() {
+            for (oscillator_index, oscillator_params) in parameters.oscillators.iter().enumerate() {
                 oscillator_params.write(
                     frequency,
-                    parameters.frequency_std_dev,
-                    rng.sample(distributions::Standard),
+                    &parameters.drift,
+                    parameters.drift.seed(
+                        parameters.frequency_walk_seed,
+                        note_index,
+                        oscillator_index,
+                    ),
+                    &pitch_factors,
                     parameters.wave_forms.as_ref(),
                     parameters.sample_rate,
                     &mut samples,
@@ -36,15 +48,19 @@ impl DataPoint {
             }
         }
 
-        let amplitude_factor = 1. / chord_type.num_notes() as f32;
+        let amplitude_factor = 1. / parameters.chord.num_notes() as f32;
         for sample in samples.iter_mut() {
             *sample *= amplitude_factor;
         }
 
+        if let Some(tremolo) = &parameters.tremolo {
+            tremolo.apply_tremolo(parameters.sample_rate, &mut samples);
+        }
+
         samples
     }
 
-    fn apply_effects(parameters: &DataPointParameters, buffer: &mut [f32]) {
+    pub(crate) fn apply_effects(parameters: &DataPointParameters, buffer: &mut [f32]) {
         let total_amplitude = parameters
             .oscillators
             .iter()
@@ -73,9 +89,28 @@ impl DataPoint {
         &self.parameters
     }
 
+    /// Realized frequency of each note for each sample. See [`DataPointParameters::frequency_trajectory`].
+    pub fn frequency_trajectories(&self) -> Vec<Vec<f32>> {
+        (0..self.parameters.frequencies.len())
+            .map(|note_index| self.parameters.frequency_trajectory(note_index))
+            .collect()
+    }
+
     pub fn label(&self) -> DataPointLabel {
         DataPointLabel::new(&self.parameters)
     }
+
+    pub fn frame_labels(&self, framing: Framing) -> FrameLabels {
+        let mut labels =
+            FrameLabels::empty(self.audio.sample_rate, self.audio.num_samples(), framing);
+        labels.add_event(0, &self.parameters);
+        labels
+    }
+
+    /// The notes of the data point at full velocity. See [`MidiFile::to_bytes`] for how detuning is written.
+    pub fn midi_file(&self) -> MidiFile {
+        MidiFile::new(MidiFile::parameter_notes(&self.parameters, 0., 1.).collect())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -85,21 +120,120 @@ pub struct DataPointLabel {
     pub frequencies: Option<Vec<f32>>,
     pub note: Option<u32>,
     pub chord_type: u32,
+    /// Name of the chord in the registry it was generated from.
+    #[serde(default)]
+    pub chord_name: Option<String>,
+    /// Intervals of the chord above the root in semitones.
+    #[serde(default)]
+    pub chord_intervals: Option<Vec<u32>>,
+    /// Chord symbol such as `C#m7` or `Bbmaj9/D`, if the chord has a standard quality.
+    #[serde(default)]
+    pub chord_symbol: Option<String>,
+    /// Key of the progression the chord is part of, such as `A minor`.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Function of the chord in the key, such as `V7` or `bVI`.
+    #[serde(default)]
+    pub roman_numeral: Option<String>,
+    /// Tuning the chord intervals were generated in.
+    #[serde(default)]
+    pub tuning: Option<Tuning>,
+    /// Frequency of A4 the notes were tuned to. Labels without it were tuned to [`crate::A4_FREQUENCY`].
+    #[serde(default)]
+    pub a4_frequency: Option<f32>,
+    /// Index of the lowest note of the chord, where 0 is the root. Only known for explicitly voiced chords.
+    #[serde(default)]
+    pub inversion: Option<u32>,
+    /// Pitch class of the lowest MIDI note, with C as 0. Left out for tunings without pitch classes, as is the chord
+    /// symbol. See [`Tuning::pitch_class`].
+    #[serde(default)]
+    pub bass_note: Option<u32>,
+    /// Sorted MIDI note numbers of all frequencies, from which the multi-hot vectors are derived.
+    #[serde(default)]
+    pub midi_notes: Option<Vec<u32>>,
+    /// [`DataPointLabel::pitch_class_vector`] when the label was created, for consumers that only read the JSON.
+    #[serde(default)]
+    pub chord_multi_hot: Option<Vec<f32>>,
+    /// [`DataPointLabel::midi_note_vector`] when the label was created, for consumers that only read the JSON.
+    #[serde(default)]
+    pub note_multi_hot: Option<Vec<f32>>,
+    /// MIDI note number the base frequency was sampled from before detuning.
+    #[serde(default)]
+    pub intended_note: Option<u32>,
+    /// Detune of the base frequency from the intended note in cents.
+    #[serde(default)]
+    pub detune: Option<f32>,
     pub num_samples: u64,
+    /// Peak pitch deviation of the vibrato in cents.
+    #[serde(default)]
+    pub vibrato_depth: Option<f32>,
+    /// Number of voices and total detune in cents of every oscillator that is played in unison. Left out if no
+    /// oscillator is.
+    #[serde(default)]
+    pub unison: Option<Vec<(u32, f32)>>,
 }
 
 impl DataPointLabel {
     pub fn new(params: &DataPointParameters) -> Self {
+        let midi_notes = params.midi_notes();
+        let note = crate::note_number_to_note(
+            params
+                .tuning
+                .frequency_to_note_number(params.base_frequency, params.a4_frequency),
+        );
+        let bass_note = midi_notes
+            .first()
+            .and_then(|&note| params.tuning.pitch_class(note));
         Self {
             sample_rate: params.sample_rate,
             base_frequency: Some(params.base_frequency),
             frequencies: Some(params.frequencies.clone()),
-            note: Some(crate::note_number_to_note(crate::frequency_to_note_number(
-                params.base_frequency,
-            ))),
+            note: Some(note),
             chord_type: params.chord_type,
+            chord_name: Some(params.chord_name.clone()),
+            chord_intervals: Some(params.chord.intervals().to_vec()),
+            chord_symbol: params
+                .tuning
+                .pitch_class(note)
+                .and_then(|root| ChordSymbol::new(root, params.chord.clone(), bass_note))
+                .map(|symbol| symbol.to_string()),
+            key: params
+                .progression_chord
+                .as_ref()
+                .map(|progression_chord| progression_chord.key.to_string()),
+            roman_numeral: params
+                .progression_chord
+                .as_ref()
+                .map(|progression_chord| progression_chord.roman_numeral()),
+            tuning: Some(params.tuning.clone()),
+            a4_frequency: Some(params.a4_frequency),
+            inversion: params.inversion.map(|inversion| inversion as u32),
+            bass_note,
+            midi_notes: Some(midi_notes),
+            chord_multi_hot: None,
+            note_multi_hot: None,
+            intended_note: params.note.map(|(note_number, _)| note_number),
+            detune: params.note.map(|(_, detune)| detune),
             num_samples: params.num_samples,
+            vibrato_depth: params.vibrato.as_ref().map(|vibrato| vibrato.depth()),
+            unison: Some(
+                params
+                    .oscillators
+                    .iter()
+                    .filter_map(|oscillator| oscillator.unison())
+                    .map(|unison| (unison.num_voices() as u32, unison.detune()))
+                    .collect::<Vec<_>>(),
+            )
+            .filter(|unison| !unison.is_empty()),
         }
+        .with_multi_hot_vectors()
+    }
+
+    /// Stores the multi-hot vectors derived from `midi_notes` in the label, so they are serialized with it.
+    pub fn with_multi_hot_vectors(mut self) -> Self {
+        self.chord_multi_hot = self.pitch_class_vector().map(Vec::from);
+        self.note_multi_hot = self.midi_note_vector().map(Vec::from);
+        self
     }
 
     pub fn sample_rate(&self) -> u32 {
@@ -119,7 +253,10 @@ impl DataPointLabel {
     }
 
     pub fn note_number(&self) -> Option<f32> {
-        self.base_frequency.map(crate::frequency_to_note_number)
+        self.base_frequency.map(|frequency| match &self.tuning {
+            Some(tuning) => tuning.frequency_to_note_number(frequency, self.a4_frequency()),
+            None => crate::frequency_to_note_number_with_reference(frequency, self.a4_frequency()),
+        })
     }
 
     pub fn note(&self) -> Option<u32> {
@@ -130,9 +267,94 @@ impl DataPointLabel {
         self.chord_type
     }
 
+    pub fn chord_name(&self) -> Option<&str> {
+        self.chord_name.as_deref()
+    }
+
+    pub fn chord_intervals(&self) -> Option<&[u32]> {
+        self.chord_intervals.as_deref()
+    }
+
+    pub fn chord_symbol(&self) -> Option<&str> {
+        self.chord_symbol.as_deref()
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn roman_numeral(&self) -> Option<&str> {
+        self.roman_numeral.as_deref()
+    }
+
+    pub fn tuning(&self) -> Option<&Tuning> {
+        self.tuning.as_ref()
+    }
+
+    pub fn inversion(&self) -> Option<u32> {
+        self.inversion
+    }
+
+    pub fn bass_note(&self) -> Option<u32> {
+        self.bass_note
+    }
+
+    pub fn midi_notes(&self) -> Option<&[u32]> {
+        self.midi_notes.as_deref()
+    }
+
+    /// Multi-hot vector of the active pitch classes, with C at index 0.
+    pub fn pitch_class_vector(&self) -> Option<[f32; 12]> {
+        let mut vector = [0.; 12];
+        for &note in self.midi_notes.as_ref()? {
+            vector[note as usize % 12] = 1.;
+        }
+        Some(vector)
+    }
+
+    /// Multi-hot vector of the active MIDI notes.
+    pub fn midi_note_vector(&self) -> Option<[f32; 128]> {
+        self.note_vector(0)
+    }
+
+    /// Multi-hot vector of the active piano keys, from A0 at index 0 to C8. Notes outside the piano are left out.
+    pub fn piano_key_vector(&self) -> Option<[f32; 88]> {
+        self.note_vector(PIANO_LOWEST_NOTE)
+    }
+
+    /// Multi-hot vector of the active MIDI notes starting at the note `offset`.
+    fn note_vector<const N: usize>(&self, offset: u32) -> Option<[f32; N]> {
+        let mut vector = [0.; N];
+        for &note in self.midi_notes.as_ref()? {
+            if let Some(value) = note
+                .checked_sub(offset)
+                .and_then(|index| vector.get_mut(index as usize))
+            {
+                *value = 1.;
+            }
+        }
+        Some(vector)
+    }
+
+    pub fn intended_note(&self) -> Option<u32> {
+        self.intended_note
+    }
+
+    pub fn detune(&self) -> Option<f32> {
+        self.detune
+    }
+
+    pub fn a4_frequency(&self) -> f32 {
+        self.a4_frequency.unwrap_or(crate::A4_FREQUENCY)
+    }
+
     pub fn num_samples(&self) -> u64 {
         self.num_samples
     }
+
+    pub fn vibrato_depth(&self) -> Option<f32> {
+        self.vibrato_depth
+    }
 }
 
 pub fn load_dir<P>(path: P) -> anyhow::Result<Vec<(Audio, DataPointLabel)>>
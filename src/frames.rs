@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    data::DataPointLabel,
+    parameters::DataPointParameters,
+};
+
+/// How audio is split into frames, matching an STFT without padding: frame `i` covers the samples from
+/// `i * hop_size` to `i * hop_size + window_size`, and only frames that fit entirely within the audio are used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Framing {
+    pub hop_size: usize,
+    pub window_size: usize,
+}
+
+impl Framing {
+    pub fn new(hop_size: usize, window_size: usize) -> Result<Self, ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(hop_size > 0, || ConfigError::Zero("hop_size"));
+        violations.check(window_size > 0, || ConfigError::Zero("window_size"));
+        violations.into_result()?;
+        Ok(Self {
+            hop_size,
+            window_size,
+        })
+    }
+
+    pub fn num_frames(&self, num_samples: usize) -> usize {
+        if num_samples < self.window_size {
+            0
+        } else {
+            (num_samples - self.window_size) / self.hop_size + 1
+        }
+    }
+
+    /// Sample at the center of a frame, at which its labels are taken.
+    pub fn center(&self, frame: usize) -> usize {
+        frame * self.hop_size + self.window_size / 2
+    }
+}
+
+/// Labels of a single frame.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FrameLabel {
+    /// Whether any pitched note is sounding.
+    pub voiced: bool,
+    /// Realized frequency of the root of the most recent note or chord, including drift and vibrato.
+    pub f0: Option<f32>,
+    /// Realized frequencies of all sounding notes.
+    pub frequencies: Vec<f32>,
+    /// Sorted MIDI note numbers of the realized frequencies without duplicates.
+    pub midi_notes: Vec<u32>,
+    /// Chord symbol of the most recent note or chord.
+    pub chord_symbol: Option<String>,
+}
+
+/// Frame-wise labels of a data point or sequence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FrameLabels {
+    pub sample_rate: u32,
+    pub framing: Framing,
+    pub frames: Vec<FrameLabel>,
+}
+
+impl FrameLabels {
+    /// Time of the center of a frame in seconds.
+    pub fn time(&self, frame: usize) -> f32 {
+        self.framing.center(frame) as f32 / self.sample_rate as f32
+    }
+
+    /// Fills the frames with the notes of an event that starts at `onset` in the clip. Events must be added in
+    /// order of their onsets.
+    pub(crate) fn add_event(&mut self, onset: usize, parameters: &DataPointParameters) {
+        let end = onset + parameters.num_samples as usize;
+        let frames: Vec<usize> = (0..self.frames.len())
+            .filter(|&frame| (onset..end).contains(&self.framing.center(frame)))
+            .collect();
+        if frames.is_empty() {
+            return;
+        }
+
+        let chord_symbol = DataPointLabel::new(parameters).chord_symbol;
+        let trajectories: Vec<Vec<f32>> = if parameters.has_frequency() {
+            (0..parameters.frequencies.len())
+                .map(|note_index| parameters.frequency_trajectory(note_index))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for frame in frames {
+            let position = self.framing.center(frame) - onset;
+            let label = &mut self.frames[frame];
+            for trajectory in trajectories.iter() {
+                let frequency = trajectory[position];
+                label.frequencies.push(frequency);
+                label.midi_notes.push(parameters.midi_note(frequency));
+            }
+            label.midi_notes.sort_unstable();
+            label.midi_notes.dedup();
+            label.f0 = trajectories.first().map(|trajectory| trajectory[position]);
+            label.voiced |= !trajectories.is_empty();
+            label.chord_symbol = chord_symbol.clone();
+        }
+    }
+
+    pub(crate) fn empty(sample_rate: u32, num_samples: usize, framing: Framing) -> Self {
+        Self {
+            sample_rate,
+            framing,
+            frames: vec![FrameLabel::default(); framing.num_frames(num_samples)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FrameLabels, Framing};
+    use crate::parameters::{DataParameters, DataPointParameters};
+
+    #[test]
+    fn frames_are_labelled_at_their_centers() {
+        let framing = Framing::new(100, 200).unwrap();
+        assert_eq!(framing.num_frames(150), 0);
+        assert_eq!(framing.num_frames(1000), 9);
+        assert_eq!(framing.center(2), 300);
+
+        let data_parameters = DataParameters::load_config("configs/synth.toml").unwrap();
+        let mut parameters = DataPointParameters::new(&data_parameters, 0);
+        parameters.num_samples = 300;
+        let mut labels = FrameLabels::empty(44100, 1000, framing);
+        labels.add_event(300, &parameters);
+
+        // Only the frames centered on samples 300, 400 and 500 fall within the event.
+        let voiced: Vec<bool> = labels.frames.iter().map(|frame| frame.voiced).collect();
+        assert_eq!(
+            voiced,
+            [false, false, true, true, true, false, false, false, false]
+        );
+        assert_eq!(
+            labels.frames[2].frequencies.len(),
+            parameters.frequencies.len()
+        );
+        assert!((labels.time(2) - 300. / 44100.).abs() < 1e-6);
+    }
+}
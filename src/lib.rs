@@ -1,35 +1,59 @@
 use std::{
     collections::hash_map::DefaultHasher,
-    f32::consts::SQRT_2,
     hash::{Hash, Hasher},
 };
 
 mod audio;
-mod chord;
+pub mod chord;
+pub mod chord_symbol;
+pub mod config;
 pub mod data;
 pub mod effects;
+pub mod frames;
 pub mod log_uniform;
+pub mod melody;
+pub mod midi;
+pub mod wave_form_extraction;
 
 pub mod parameters;
+pub mod scala;
+pub mod sequence;
+pub mod theory;
+pub mod tuning;
 pub use audio::Audio;
-use chord::ChordType;
 use rand::prelude::Distribution;
 use serde::{Deserialize, Serialize};
 
 const FREQUENCY_MAP_RANGE: (f32, f32) = (20., 20000.);
-const A4_FREQUENCY: f32 = 440.0;
-const A4_NOTE_NUMBER: f32 = 69.0;
-
-pub const CHORD_TYPES: &[(&str, ChordType)] = &[
-    ("Single Note", ChordType::new(&[])),
-    ("Power", ChordType::new(&[3. / 2.])),
-    ("Major", ChordType::new(&[5. / 4., 3. / 2.])),
-    ("Minor", ChordType::new(&[6. / 5., 3. / 2.])),
-    ("Diminished", ChordType::new(&[6. / 5., SQRT_2])),
-    ("Sus", ChordType::new(&[4. / 3., 3. / 2.])),
-    ("Major 7th", ChordType::new(&[5. / 4., 3. / 2., 15. / 8.])),
-    ("Minor 7th", ChordType::new(&[6. / 5., 3. / 2., 9. / 5.])),
-    ("Dominant 7th", ChordType::new(&[5. / 4., 3. / 2., 9. / 5.])),
+/// Default concert pitch.
+pub const A4_FREQUENCY: f32 = 440.0;
+pub const A4_NOTE_NUMBER: f32 = 69.0;
+
+/// The chords in the default [`chord::ChordRegistry`] as intervals above the root in semitones.
+pub const CHORD_TYPES: &[(&str, &[u32])] = &[
+    ("Single Note", &[]),
+    ("Power", &[7]),
+    ("Major", &[4, 7]),
+    ("Minor", &[3, 7]),
+    ("Diminished", &[3, 6]),
+    ("Sus", &[5, 7]),
+    ("Major 7th", &[4, 7, 11]),
+    ("Minor 7th", &[3, 7, 10]),
+    ("Dominant 7th", &[4, 7, 10]),
+    ("Augmented", &[4, 8]),
+    ("Sus2", &[2, 7]),
+    ("Major 6th", &[4, 7, 9]),
+    ("Minor 6th", &[3, 7, 9]),
+    ("Half-Diminished 7th", &[3, 6, 10]),
+    ("Diminished 7th", &[3, 6, 9]),
+    ("Major 9th", &[4, 7, 11, 14]),
+    ("Minor 9th", &[3, 7, 10, 14]),
+    ("Dominant 9th", &[4, 7, 10, 14]),
+    ("Add 9", &[4, 7, 14]),
+    // The 11th clashes with the major third, so as is common practice the 11th chord leaves out the third and the
+    // 13th chord leaves out the 11th.
+    ("Dominant 11th", &[7, 10, 14, 17]),
+    ("Dominant 13th", &[4, 7, 10, 14, 21]),
 ];
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -42,6 +66,11 @@ impl UniformI {
     pub fn new(min: usize, max: usize) -> Self {
         Self { min, max }
     }
+
+    /// The inclusive minimum and exclusive maximum.
+    pub(crate) fn range(&self) -> (usize, usize) {
+        (self.min, self.max)
+    }
 }
 
 impl Distribution<usize> for UniformI {
@@ -50,6 +79,30 @@ impl Distribution<usize> for UniformI {
     }
 }
 
+/// Uniform distribution over counts from `min` to `max`, both inclusive, such as the number of notes in a melody.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct UniformCount {
+    min: usize,
+    max: usize,
+}
+
+impl UniformCount {
+    pub fn new(min: usize, max: usize) -> Self {
+        Self { min, max }
+    }
+
+    /// The inclusive minimum and maximum.
+    pub(crate) fn range(&self) -> (usize, usize) {
+        (self.min, self.max)
+    }
+}
+
+impl Distribution<usize> for UniformCount {
+    fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        rand::distributions::Uniform::new_inclusive(self.min, self.max).sample(rng)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct UniformF {
     min: f32,
@@ -64,11 +117,21 @@ impl UniformF {
     pub fn new_inclusive(min: f32, max: f32) -> Self {
         Self { min, max }
     }
+
+    pub(crate) fn range(&self) -> (f32, f32) {
+        (self.min, self.max)
+    }
 }
 
 impl Distribution<f32> for UniformF {
     fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> f32 {
-        rand::distributions::Uniform::new(self.min, self.max).sample(rng)
+        // Fixed values such as a drift dampening of `{ min = 0.9, max = 0.9 }` are allowed, but `Uniform` panics on
+        // an empty range.
+        if self.min == self.max {
+            self.min
+        } else {
+            rand::distributions::Uniform::new(self.min, self.max).sample(rng)
+        }
     }
 }
 
@@ -104,17 +167,64 @@ pub fn map_to_frequency(map: f32) -> f32 {
 }
 
 pub fn map_to_note_number(map: f32) -> f32 {
-    let a4_map = frequency_to_map(A4_FREQUENCY);
+    map_to_note_number_with_reference(map, A4_FREQUENCY)
+}
+
+/// Like [`map_to_note_number`], with A4 tuned to `a4_frequency` instead of [`A4_FREQUENCY`].
+pub fn map_to_note_number_with_reference(map: f32, a4_frequency: f32) -> f32 {
+    let a4_map = frequency_to_map(a4_frequency);
     A4_NOTE_NUMBER + (map - a4_map) * note_number_per_map()
 }
 
 pub fn note_number_to_map(note_number: f32) -> f32 {
-    let a4_map = frequency_to_map(A4_FREQUENCY);
+    note_number_to_map_with_reference(note_number, A4_FREQUENCY)
+}
+
+/// Like [`note_number_to_map`], with A4 tuned to `a4_frequency` instead of [`A4_FREQUENCY`].
+pub fn note_number_to_map_with_reference(note_number: f32, a4_frequency: f32) -> f32 {
+    let a4_map = frequency_to_map(a4_frequency);
     a4_map + (note_number - A4_NOTE_NUMBER) / note_number_per_map()
 }
 
 pub fn frequency_to_note_number(frequency: f32) -> f32 {
-    69.0 + 12.0 * (frequency / 440.0).log2()
+    frequency_to_note_number_with_reference(frequency, A4_FREQUENCY)
+}
+
+/// Like [`frequency_to_note_number`], with A4 tuned to `a4_frequency` instead of [`A4_FREQUENCY`].
+pub fn frequency_to_note_number_with_reference(frequency: f32, a4_frequency: f32) -> f32 {
+    A4_NOTE_NUMBER + 12.0 * (frequency / a4_frequency).log2()
+}
+
+pub fn note_number_to_frequency(note_number: f32) -> f32 {
+    note_number_to_frequency_with_reference(note_number, A4_FREQUENCY)
+}
+
+/// Like [`note_number_to_frequency`], with A4 tuned to `a4_frequency` instead of [`A4_FREQUENCY`].
+pub fn note_number_to_frequency_with_reference(note_number: f32, a4_frequency: f32) -> f32 {
+    a4_frequency * ((note_number - A4_NOTE_NUMBER) / 12.0).exp2()
+}
+
+/// Parses a note name in scientific pitch notation such as `C4`, `F#3` or `Bb-1` into a MIDI note number.
+pub fn note_name_to_note_number(name: &str) -> Option<f32> {
+    let mut chars = name.chars();
+    let pitch_class: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let octave = rest.trim_start_matches(['#', 'b']);
+    let alteration: i32 = rest[..rest.len() - octave.len()]
+        .chars()
+        .map(|accidental| if accidental == '#' { 1 } else { -1 })
+        .sum();
+    let octave: i32 = octave.parse().ok()?;
+    Some((12 * (octave + 1) + pitch_class + alteration) as f32)
 }
 
 pub fn note_number_to_note(note_number: f32) -> u32 {
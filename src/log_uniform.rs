@@ -1,6 +1,8 @@
 use rand::{prelude::Distribution, Rng};
 use serde::{Deserialize, Serialize};
 
+use crate::config::ConfigError;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogUniform {
     min: f32,
@@ -8,9 +10,20 @@ pub struct LogUniform {
 }
 
 impl LogUniform {
-    pub fn from_tuple(mut range: (f32, f32)) -> Self {
-        assert!(range.0 >= 0.0, "Range must be non-negative.");
-        assert!(range.1 >= range.0);
+    /// A minimum of 0 is replaced by a small positive value, since the distribution is uniform in the logarithm.
+    pub fn from_tuple(range: (f32, f32)) -> Result<Self, ConfigError> {
+        if !(0.0 <= range.0 && range.0 <= range.1 && range.1.is_finite()) {
+            return Err(ConfigError::InvalidRange {
+                name: "log-uniform",
+                range: (range.0 as f64, range.1 as f64),
+                requirement: "the bounds must be finite, the minimum non-negative and no greater than the maximum",
+            });
+        }
+        Ok(Self::from_valid_tuple(range))
+    }
+
+    /// Like [`LogUniform::from_tuple`] for ranges the caller has already checked.
+    pub(crate) fn from_valid_tuple(mut range: (f32, f32)) -> Self {
         if range.0 == 0.0 {
             range.0 = 1e-6;
 
@@ -24,6 +37,19 @@ impl LogUniform {
         }
     }
 
+    /// Checks a deserialized distribution, whose minimum must be positive.
+    pub(crate) fn validate(&self, name: &'static str) -> Result<(), ConfigError> {
+        if 0.0 < self.min && self.min <= self.max && self.max.is_finite() {
+            Ok(())
+        } else {
+            Err(ConfigError::InvalidRange {
+                name,
+                range: (self.min as f64, self.max as f64),
+                requirement: "the bounds must be finite, the minimum positive and no greater than the maximum",
+            })
+        }
+    }
+
     pub fn max(&self) -> f32 {
         self.max
     }
@@ -0,0 +1,480 @@
+use std::path::Path;
+
+use rand::{prelude::Distribution, seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, ConfigError, Violations},
+    data::DataPoint,
+    hash,
+    parameters::{DataParameters, DataPointParameters},
+    theory::{Key, Mode},
+    Audio, UniformCount, UniformF,
+};
+
+/// How the notes of a melodic line are chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pattern {
+    /// The notes of the chord from the lowest to the highest, continuing with the root an octave up.
+    ArpeggioUp,
+    /// The notes of the chord from the highest to the lowest.
+    ArpeggioDown,
+    /// A random note of the chord at every step.
+    ArpeggioRandom,
+    /// Lowest, highest, middle, highest note of the chord, as in an Alberti bass.
+    Alberti,
+    /// Consecutive degrees of the scale from the root, upwards or downwards.
+    ScaleRun,
+    /// Steps of one or two scale degrees in a random direction.
+    RandomWalk,
+}
+
+/// Attack, decay, sustain, release envelope applied to every note.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+}
+
+impl Envelope {
+    /// Times are in seconds and `sustain` is the level held after the decay.
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32) -> Result<Self, ConfigError> {
+        let envelope = Self {
+            attack,
+            decay,
+            sustain,
+            release,
+        };
+        envelope.validate()?;
+        Ok(envelope)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        for (name, time) in [
+            ("Envelope attack", self.attack),
+            ("Envelope decay", self.decay),
+            ("Envelope release", self.release),
+        ] {
+            violations.check(time.is_finite() && time >= 0., || ConfigError::OutOfRange {
+                name,
+                value: time as f64,
+                interval: "[0;inf)",
+            });
+        }
+        violations.check_probability("Envelope sustain", self.sustain as f64, true);
+        violations.into_result()
+    }
+
+    /// Level at `time` seconds into a note of `length` seconds. The release starts before the end of the note so
+    /// that it is silent when the next note starts.
+    pub fn value(&self, time: f32, length: f32) -> f32 {
+        let level = if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1. - (1. - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        };
+        let remaining = length - time;
+        if remaining < self.release {
+            level * remaining / self.release
+        } else {
+            level
+        }
+    }
+}
+
+/// Distribution over clips of single notes in a melodic pattern over the chord or key of the clip.
+///
+/// The root, chord, timbre and effects of a clip are sampled from [`DataParameters`] as for a single data point.
+/// The key of scale patterns has the root as its tonic and one of the given modes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MelodyParameters {
+    patterns: Vec<Pattern>,
+    modes: Vec<Mode>,
+    num_notes_distribution: UniformCount,
+    note_duration_distribution: UniformF,
+    envelope: Envelope,
+}
+
+impl MelodyParameters {
+    /// `note_duration_range` is in seconds. Each clip has one pattern, note duration and number of notes.
+    pub fn new<A>(
+        patterns: A,
+        num_notes_range: (usize, usize),
+        note_duration_range: (f32, f32),
+        envelope: Envelope,
+    ) -> Result<Self, ConfigError>
+    where
+        A: Into<Vec<Pattern>>,
+    {
+        let parameters = Self {
+            patterns: patterns.into(),
+            modes: vec![Mode::Ionian],
+            num_notes_distribution: UniformCount::new(num_notes_range.0, num_notes_range.1),
+            note_duration_distribution: UniformF::new(note_duration_range.0, note_duration_range.1),
+            envelope,
+        };
+        parameters.validate()?;
+        Ok(parameters)
+    }
+
+    /// Modes the key of scale patterns is chosen from. Defaults to major.
+    pub fn with_modes<A>(mut self, modes: A) -> Result<Self, ConfigError>
+    where
+        A: Into<Vec<Mode>>,
+    {
+        self.modes = modes.into();
+        if self.modes.is_empty() {
+            return Err(ConfigError::Empty("modes"));
+        }
+        Ok(self)
+    }
+
+    /// Reads the parameters from a TOML file if the extension is `.toml` and from a JSON file otherwise, and
+    /// validates them.
+    pub fn load<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let parameters: Self = config::read(path.as_ref())?;
+        parameters.validate()?;
+        Ok(parameters)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(!self.patterns.is_empty(), || ConfigError::Empty("patterns"));
+        violations.check(!self.modes.is_empty(), || ConfigError::Empty("modes"));
+        let (min_notes, max_notes) = self.num_notes_distribution.range();
+        violations.check(0 < min_notes && min_notes <= max_notes, || {
+            ConfigError::InvalidRange {
+                name: "number of notes",
+                range: (min_notes as f64, max_notes as f64),
+                requirement: "there must be at least one note and the range must be non-empty",
+            }
+        });
+        violations.check_range(
+            "note duration",
+            self.note_duration_distribution.range(),
+            (f32::MIN_POSITIVE, f32::MAX),
+            "the range must be finite, positive and non-empty",
+        );
+        violations.add(self.envelope.validate());
+        violations.into_result()
+    }
+
+    /// Samples the notes of the clip with the given index. Notes that would start after the end of the clip are
+    /// left out.
+    pub fn generate(&self, data_parameters: &DataParameters, index: u64) -> MelodyNotes {
+        let seed = data_parameters.seed(index);
+        let mut rng = Pcg64Mcg::seed_from_u64(hash(seed));
+        let clip = DataPointParameters::new(data_parameters, seed);
+        let sample_rate = clip.sample_rate as f32;
+
+        let pattern = *self.patterns.choose(&mut rng).unwrap();
+        let key = Key::new(
+            clip.midi_note(clip.base_frequency) % 12,
+            *self.modes.choose(&mut rng).unwrap(),
+        );
+        let num_notes = self.num_notes_distribution.sample(&mut rng);
+        let note_length =
+            ((self.note_duration_distribution.sample(&mut rng) * sample_rate) as u64).max(1);
+
+        let frequencies = pattern_frequencies(pattern, &clip, key, num_notes, &mut rng);
+        let notes = frequencies
+            .into_iter()
+            .enumerate()
+            .map(|(note_index, frequency)| Note {
+                onset: note_index as u64 * note_length,
+                length: note_length,
+                frequency,
+            })
+            .take_while(|note| note.onset < clip.num_samples)
+            .map(|note| Note {
+                length: note.length.min(clip.num_samples - note.onset),
+                ..note
+            })
+            .collect();
+
+        MelodyNotes {
+            clip,
+            pattern,
+            key,
+            envelope: self.envelope,
+            notes,
+        }
+    }
+}
+
+/// Frequencies of the notes of a pattern. Arpeggios use the notes of the clip as they are voiced and spread over
+/// octaves, from low to high.
+fn pattern_frequencies<R: Rng + ?Sized>(
+    pattern: Pattern,
+    clip: &DataPointParameters,
+    key: Key,
+    num_notes: usize,
+    rng: &mut R,
+) -> Vec<f32> {
+    let mut chord = clip.frequencies.clone();
+    chord.sort_by(f32::total_cmp);
+    chord.dedup();
+    let last = chord.len() - 1;
+
+    // Scale degrees relative to the root, in the tuning of the clip.
+    let scale = key.mode.scale();
+    let degree_frequency = |degree: i32| {
+        let semitones = scale[degree.rem_euclid(7) as usize] as i32 + 12 * degree.div_euclid(7);
+        clip.base_frequency * clip.tuning.ratio(semitones)
+    };
+
+    match pattern {
+        Pattern::ArpeggioUp => {
+            // Notes within an octave are completed by the lowest note an octave up.
+            let top = (chord[last] < chord[0] * 2.).then_some(chord[0] * 2.);
+            chord
+                .iter()
+                .copied()
+                .chain(top)
+                .cycle()
+                .take(num_notes)
+                .collect()
+        }
+        Pattern::ArpeggioDown => chord
+            .iter()
+            .rev()
+            .copied()
+            .cycle()
+            .take(num_notes)
+            .collect(),
+        Pattern::ArpeggioRandom => (0..num_notes)
+            .map(|_| *chord.choose(rng).unwrap())
+            .collect(),
+        Pattern::Alberti => [0, last, last.min(1), last]
+            .into_iter()
+            .cycle()
+            .take(num_notes)
+            .map(|index| chord[index])
+            .collect(),
+        Pattern::ScaleRun => {
+            let direction = if rng.gen_bool(0.5) { 1 } else { -1 };
+            (0..num_notes as i32)
+                .map(|step| degree_frequency(step * direction))
+                .collect()
+        }
+        Pattern::RandomWalk => {
+            let mut degree = 0;
+            (0..num_notes)
+                .map(|_| {
+                    let frequency = degree_frequency(degree);
+                    degree += *[-2, -1, 1, 2].choose(rng).unwrap();
+                    frequency
+                })
+                .collect()
+        }
+    }
+}
+
+/// A single note of a melody.
+#[derive(Debug, Clone, Copy)]
+pub struct Note {
+    /// Sample at which the note starts.
+    pub onset: u64,
+    /// Length of the note in samples.
+    pub length: u64,
+    pub frequency: f32,
+}
+
+/// The sampled notes of a clip, ready to be rendered.
+#[derive(Debug, Clone)]
+pub struct MelodyNotes {
+    /// Parameters of the clip, whose oscillators, drift, vibrato, tremolo and effects are used for all notes.
+    pub clip: DataPointParameters,
+    pub pattern: Pattern,
+    pub key: Key,
+    pub envelope: Envelope,
+    pub notes: Vec<Note>,
+}
+
+impl MelodyNotes {
+    pub fn generate(self) -> Melody {
+        let clip = &self.clip;
+        let sample_rate = clip.sample_rate;
+        let mut samples = vec![0.; clip.num_samples as usize];
+        let pitch_factors = clip.vibrato_factors();
+
+        for (note_index, note) in self.notes.iter().enumerate() {
+            let range = note.onset as usize..(note.onset + note.length) as usize;
+            let mut note_samples = vec![0.; note.length as usize];
+            for (oscillator_index, oscillator) in clip.oscillators.iter().enumerate() {
+                oscillator.write(
+                    note.frequency,
+                    &clip.drift,
+                    clip.drift
+                        .seed(clip.frequency_walk_seed, note_index, oscillator_index),
+                    &pitch_factors[range.clone()],
+                    clip.wave_forms.as_ref(),
+                    sample_rate,
+                    &mut note_samples,
+                );
+            }
+            let length = note.length as f32 / sample_rate as f32;
+            for (index, (sample, note_sample)) in
+                samples[range].iter_mut().zip(note_samples).enumerate()
+            {
+                *sample += note_sample
+                    * self
+                        .envelope
+                        .value(index as f32 / sample_rate as f32, length);
+            }
+        }
+
+        if let Some(tremolo) = &clip.tremolo {
+            tremolo.apply_tremolo(sample_rate, &mut samples);
+        }
+        DataPoint::apply_effects(clip, &mut samples);
+
+        Melody {
+            audio: Audio::from_samples(samples, sample_rate),
+            notes: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Melody {
+    pub audio: Audio,
+    pub notes: MelodyNotes,
+}
+
+impl Melody {
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    pub fn label(&self) -> MelodyLabel {
+        let clip = &self.notes.clip;
+        MelodyLabel {
+            sample_rate: clip.sample_rate,
+            num_samples: clip.num_samples,
+            pattern: self.notes.pattern,
+            key: self.notes.key.to_string(),
+            chord_name: clip.chord_name.clone(),
+            notes: self
+                .notes
+                .notes
+                .iter()
+                .map(|note| NoteLabel {
+                    onset: note.onset,
+                    end: note.onset + note.length,
+                    frequency: note.frequency,
+                    midi_note: clip.midi_note(note.frequency),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoteLabel {
+    /// Sample at which the note starts.
+    pub onset: u64,
+    /// Sample after the last sample of the note.
+    pub end: u64,
+    /// Nominal frequency of the note, without drift and vibrato.
+    pub frequency: f32,
+    pub midi_note: u32,
+}
+
+/// Note-level events of a melody, ordered by onset.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MelodyLabel {
+    pub sample_rate: u32,
+    pub num_samples: u64,
+    pub pattern: Pattern,
+    pub key: String,
+    /// Name of the chord that arpeggio patterns are built from.
+    pub chord_name: String,
+    pub notes: Vec<NoteLabel>,
+}
+
+#[cfg(test)]
+mod test {
+    use rand_pcg::Pcg64Mcg;
+
+    use super::{Envelope, Pattern};
+    use crate::{
+        parameters::{DataParameters, DataPointParameters},
+        theory::{Key, Mode},
+    };
+
+    #[test]
+    fn envelope_follows_attack_decay_sustain_release() {
+        let envelope = Envelope::new(0.1, 0.1, 0.5, 0.2).unwrap();
+        let cases = [
+            (0., 0.),
+            (0.05, 0.5),
+            (0.1, 1.),
+            (0.15, 0.75),
+            (0.5, 0.5),
+            (0.9, 0.25),
+            (1., 0.),
+        ];
+        for (time, expected) in cases {
+            let value = envelope.value(time, 1.);
+            assert!(
+                (value - expected).abs() < 1e-5,
+                "Time: {time}  Value: {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn patterns_follow_the_chord_and_scale() {
+        let data_parameters = DataParameters::load_config("configs/synth_chords.toml").unwrap();
+        // Three notes spread over more than an octave, which the nominal chord never is.
+        let voiced_notes = |clip: &DataPointParameters| {
+            let mut notes = clip.frequencies.clone();
+            notes.sort_by(f32::total_cmp);
+            notes.dedup();
+            notes
+        };
+        let clip = (0..)
+            .map(|seed| DataPointParameters::new(&data_parameters, seed))
+            .find(|clip| {
+                let notes = voiced_notes(clip);
+                notes.len() == 3 && notes[2] > notes[0] * 2.
+            })
+            .unwrap();
+        let chord = voiced_notes(&clip);
+        let key = Key::new(clip.midi_note(clip.base_frequency) % 12, Mode::Ionian);
+        let frequencies =
+            |pattern| super::pattern_frequencies(pattern, &clip, key, 6, &mut Pcg64Mcg::new(1));
+
+        let expected = [chord[0], chord[1], chord[2], chord[0], chord[1], chord[2]];
+        assert_eq!(frequencies(Pattern::ArpeggioUp), expected);
+        let expected = [chord[2], chord[1], chord[0], chord[2], chord[1], chord[0]];
+        assert_eq!(frequencies(Pattern::ArpeggioDown), expected);
+        let expected = [chord[0], chord[2], chord[1], chord[2], chord[0], chord[2]];
+        assert_eq!(frequencies(Pattern::Alberti), expected);
+        assert!(frequencies(Pattern::ArpeggioRandom)
+            .iter()
+            .all(|frequency| chord.contains(frequency)));
+
+        // A major scale from the root, in either direction.
+        let scale_run = frequencies(Pattern::ScaleRun);
+        assert_eq!(scale_run[0], clip.base_frequency);
+        let steps: Vec<i32> = scale_run
+            .windows(2)
+            .map(|pair| (crate::cent_diff(pair[0], pair[1]) / 100.).round() as i32)
+            .collect();
+        assert!(
+            steps == [2, 2, 1, 2, 2] || steps == [-1, -2, -2, -2, -1],
+            "Steps: {steps:?}"
+        );
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use midly::{
+    num::{u15, u24, u28, u4, u7},
+    Format, Header, MetaMessage, MidiMessage, PitchBend, Smf, Timing, TrackEvent, TrackEventKind,
+};
+
+use crate::{
+    config::ConfigError,
+    hash,
+    parameters::{DataParameters, DataPointParameters},
+    sequence::{Event, SequenceEvents},
+};
+
+/// MIDI channel reserved for percussion in General MIDI, counted from 0. Its notes are not pitched and are skipped.
+const PERCUSSION_CHANNEL: u8 = 9;
+/// Tempo in microseconds per quarter note until the first tempo event.
+const DEFAULT_TEMPO: u32 = 500_000;
+/// Pitch bend range in semitones, the General MIDI default. Files are assumed not to change it.
+const PITCH_BEND_RANGE: f32 = 2.;
+/// Resolution of written files. At the default tempo a tick is about a millisecond.
+const TICKS_PER_BEAT: u16 = 480;
+
+#[derive(Debug)]
+pub enum MidiError {
+    Io(io::Error),
+    Parse(midly::Error),
+}
+
+impl Display for MidiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read MIDI file: {error}"),
+            Self::Parse(error) => write!(f, "Could not parse MIDI file: {error}"),
+        }
+    }
+}
+
+impl Error for MidiError {}
+
+impl From<io::Error> for MidiError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<midly::Error> for MidiError {
+    fn from(error: midly::Error) -> Self {
+        Self::Parse(error)
+    }
+}
+
+/// A note of a MIDI file with times in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MidiNote {
+    pub onset: f64,
+    pub end: f64,
+    /// Channel of the note, counted from 0.
+    pub channel: u8,
+    /// MIDI note number.
+    pub key: u8,
+    pub velocity: u8,
+    /// Pitch bend of the channel at the onset of the note in cents.
+    pub bend: f32,
+}
+
+/// Converts ticks to seconds following the tempo changes of a file.
+struct TempoMap {
+    /// Tick, time in seconds and seconds per tick from each tempo change on.
+    segments: Vec<(u64, f64, f64)>,
+}
+
+impl TempoMap {
+    fn new(timing: Timing, mut tempo_changes: Vec<(u64, u32)>) -> Self {
+        let segments = match timing {
+            Timing::Metrical(ticks_per_beat) => {
+                let ticks_per_beat = ticks_per_beat.as_int() as f64;
+                let seconds_per_tick = |tempo: u32| tempo as f64 * 1e-6 / ticks_per_beat;
+                tempo_changes.sort_by_key(|&(tick, _)| tick);
+                let mut segments = vec![(0, 0., seconds_per_tick(DEFAULT_TEMPO))];
+                for (tick, tempo) in tempo_changes {
+                    let &(last_tick, last_time, last_seconds_per_tick) = segments.last().unwrap();
+                    let time = last_time + (tick - last_tick) as f64 * last_seconds_per_tick;
+                    segments.push((tick, time, seconds_per_tick(tempo)));
+                }
+                segments
+            }
+            // Timecode timing counts ticks in fractions of a frame, independent of tempo.
+            Timing::Timecode(fps, ticks_per_frame) => {
+                vec![(0, 0., 1. / (fps.as_f32() as f64 * ticks_per_frame as f64))]
+            }
+        };
+        Self { segments }
+    }
+
+    fn seconds(&self, tick: u64) -> f64 {
+        let &(start_tick, start_time, seconds_per_tick) = self
+            .segments
+            .iter()
+            .rev()
+            .find(|&&(start_tick, _, _)| start_tick <= tick)
+            .unwrap();
+        start_time + (tick - start_tick) as f64 * seconds_per_tick
+    }
+}
+
+/// The pitched notes of a Standard MIDI File.
+#[derive(Clone, Debug)]
+pub struct MidiFile {
+    notes: Vec<MidiNote>,
+}
+
+impl MidiFile {
+    pub fn load<P>(path: P) -> Result<Self, MidiError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// Parses a Standard MIDI File. All tracks are played at the same time and notes on the percussion channel
+    /// are left out. A note that is started again before it ends is ended by the first note off, and notes that
+    /// never end are dropped.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MidiError> {
+        let smf = Smf::parse(bytes)?;
+
+        let mut tempo_changes = Vec::new();
+        // Onset tick, end tick, channel, key, velocity and bend of each note.
+        let mut tick_notes = Vec::new();
+        for track in smf.tracks.iter() {
+            let mut tick = 0;
+            // Onset tick, velocity and bend of the sounding notes of each channel and key.
+            let mut active = HashMap::<_, VecDeque<_>>::new();
+            let mut bends = [0.; 16];
+            for event in track.iter() {
+                tick += event.delta.as_int() as u64;
+                match event.kind {
+                    TrackEventKind::Meta(MetaMessage::Tempo(tempo)) => {
+                        tempo_changes.push((tick, tempo.as_int()))
+                    }
+                    TrackEventKind::Midi { channel, message } => {
+                        let channel = channel.as_int();
+                        if channel == PERCUSSION_CHANNEL {
+                            continue;
+                        }
+                        match message {
+                            MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => active
+                                .entry((channel, key.as_int()))
+                                .or_default()
+                                .push_back((tick, vel.as_int(), bends[channel as usize])),
+                            MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                                let key = key.as_int();
+                                if let Some((onset, velocity, bend)) = active
+                                    .get_mut(&(channel, key))
+                                    .and_then(|onsets| onsets.pop_front())
+                                {
+                                    tick_notes.push((onset, tick, channel, key, velocity, bend));
+                                }
+                            }
+                            MidiMessage::PitchBend { bend } => {
+                                bends[channel as usize] = bend.as_f32() * PITCH_BEND_RANGE * 100.
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let tempo_map = TempoMap::new(smf.header.timing, tempo_changes);
+        let notes = tick_notes
+            .into_iter()
+            .map(|(onset, end, channel, key, velocity, bend)| MidiNote {
+                onset: tempo_map.seconds(onset),
+                end: tempo_map.seconds(end),
+                channel,
+                key,
+                velocity,
+                bend,
+            })
+            .collect();
+        Ok(Self::new(notes))
+    }
+
+    /// Creates a file from notes in any order.
+    pub fn new(mut notes: Vec<MidiNote>) -> Self {
+        notes.sort_by(|a, b| a.onset.total_cmp(&b.onset).then(a.key.cmp(&b.key)));
+        Self { notes }
+    }
+
+    /// The notes of a data point starting at `onset` seconds, with each frequency rounded to the nearest equal
+    /// tempered key at the concert pitch of the data point and the rest as bend. Keys are equal tempered whatever
+    /// the tuning of the data point, as players of the file assume. Frequencies outside the MIDI range are left out.
+    pub(crate) fn parameter_notes(
+        parameters: &DataPointParameters,
+        onset: f64,
+        gain: f32,
+    ) -> impl Iterator<Item = MidiNote> + '_ {
+        let end = onset + parameters.num_samples as f64 / parameters.sample_rate as f64;
+        let velocity = (gain * 127.).round().clamp(1., 127.) as u8;
+        parameters.frequencies.iter().filter_map(move |&frequency| {
+            let note_number =
+                crate::frequency_to_note_number_with_reference(frequency, parameters.a4_frequency);
+            let key = note_number.round();
+            (0. ..=127.).contains(&key).then_some(MidiNote {
+                onset,
+                end,
+                channel: 0,
+                key: key as u8,
+                velocity,
+                bend: (note_number - key) * 100.,
+            })
+        })
+    }
+
+    /// Encodes the notes as a single track Standard MIDI File at the default tempo.
+    ///
+    /// Channels are reassigned so that notes sounding at the same time are on different channels and each can
+    /// have its own pitch bend. The percussion channel is not used. With more than 15 simultaneous notes, the
+    /// channel that became free first is shared.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let seconds_per_tick = DEFAULT_TEMPO as f64 * 1e-6 / TICKS_PER_BEAT as f64;
+        let channels: Vec<u8> = (0..16)
+            .filter(|&channel| channel != PERCUSSION_CHANNEL)
+            .collect();
+        let mut channel_ends = vec![0; channels.len()];
+
+        // Tick, order within the tick and event. Note offs come first so a channel can be reused at once.
+        let mut events = vec![(
+            0,
+            0,
+            TrackEventKind::Meta(MetaMessage::Tempo(u24::new(DEFAULT_TEMPO))),
+        )];
+        for note in self.notes.iter() {
+            let onset = (note.onset / seconds_per_tick).round() as u64;
+            let end = ((note.end / seconds_per_tick).round() as u64).max(onset + 1);
+            let slot = channel_ends
+                .iter()
+                .position(|&channel_end| channel_end <= onset)
+                .unwrap_or_else(|| {
+                    (0..channel_ends.len())
+                        .min_by_key(|&slot| channel_ends[slot])
+                        .unwrap()
+                });
+            channel_ends[slot] = end.max(channel_ends[slot]);
+            let channel = u4::new(channels[slot]);
+            let key = u7::new(note.key);
+            let bend = PitchBend::from_f32(note.bend / (PITCH_BEND_RANGE * 100.));
+            events.push((
+                onset,
+                2,
+                TrackEventKind::Midi {
+                    channel,
+                    message: MidiMessage::PitchBend { bend },
+                },
+            ));
+            events.push((
+                onset,
+                3,
+                TrackEventKind::Midi {
+                    channel,
+                    message: MidiMessage::NoteOn {
+                        key,
+                        vel: u7::new(note.velocity),
+                    },
+                },
+            ));
+            events.push((
+                end,
+                1,
+                TrackEventKind::Midi {
+                    channel,
+                    message: MidiMessage::NoteOff {
+                        key,
+                        vel: u7::new(0),
+                    },
+                },
+            ));
+        }
+        events.sort_by_key(|&(tick, order, _)| (tick, order));
+
+        let mut last_tick = 0;
+        let mut track: Vec<TrackEvent> = events
+            .into_iter()
+            .map(|(tick, _, kind)| {
+                let delta = u28::new((tick - last_tick) as u32);
+                last_tick = tick;
+                TrackEvent { delta, kind }
+            })
+            .collect();
+        track.push(TrackEvent {
+            delta: u28::new(0),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
+
+        let smf = Smf {
+            header: Header::new(
+                Format::SingleTrack,
+                Timing::Metrical(u15::new(TICKS_PER_BEAT)),
+            ),
+            tracks: vec![track],
+        };
+        let mut bytes = Vec::new();
+        smf.write_std(&mut bytes)
+            .expect("Writing to a vector cannot fail.");
+        bytes
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), MidiError>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+
+    /// Notes ordered by onset.
+    pub fn notes(&self) -> &[MidiNote] {
+        &self.notes
+    }
+
+    /// Time at which the last note ends in seconds.
+    pub fn duration(&self) -> f64 {
+        self.notes.iter().map(|note| note.end).fold(0., f64::max)
+    }
+
+    /// Turns the notes into the events of a sequence with a randomized timbre for each channel.
+    ///
+    /// The timbre of each channel is sampled from `data_parameters` and its vibrato, tremolo and drift are sampled
+    /// for each note. The effects of the whole clip are sampled once, and the concert pitch and tuning of the clip
+    /// are used for all notes. The pitch bend of each note detunes it and its gain is its velocity divided by 127.
+    /// The clip lasts until the last note ends.
+    ///
+    /// Fails if the single note chord is not registered in `data_parameters`.
+    pub fn render(
+        &self,
+        data_parameters: &DataParameters,
+        index: u64,
+    ) -> Result<SequenceEvents, ConfigError> {
+        let seed = data_parameters.seed(index);
+        let sample_rate = data_parameters.sample_rate() as f64;
+        let clip = DataPointParameters::new(data_parameters, seed);
+        let num_samples = ((self.duration() * sample_rate).ceil() as u64).max(1);
+
+        let mut timbres = HashMap::new();
+        let mut events = Vec::with_capacity(self.notes.len());
+        for (note_index, note) in self.notes.iter().enumerate() {
+            let onset = (note.onset * sample_rate) as u64;
+            if onset >= num_samples {
+                continue;
+            }
+            let end = ((note.end * sample_rate) as u64).clamp(onset + 1, num_samples);
+            let oscillators = timbres
+                .entry(note.channel)
+                .or_insert_with(|| {
+                    DataPointParameters::new(
+                        data_parameters,
+                        hash(seed ^ hash(note.channel as u64 + 1)),
+                    )
+                    .oscillators
+                })
+                .clone();
+            let mut parameters = DataPointParameters::with_note(
+                data_parameters,
+                hash(seed.wrapping_add(note_index as u64 + 1)),
+                note.key as u32,
+                note.bend,
+                clip.a4_frequency,
+            )?;
+            parameters.num_samples = end - onset;
+            parameters.oscillators = oscillators;
+            parameters.effects = Vec::new();
+            events.push(Event {
+                onset,
+                gain: note.velocity as f32 / 127.,
+                parameters,
+            });
+        }
+
+        Ok(SequenceEvents {
+            clip,
+            events,
+            num_samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use midly::{
+        num::{u15, u24, u28, u4, u7},
+        Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind,
+    };
+
+    use super::{MidiFile, MidiNote};
+
+    #[test]
+    fn note_times_follow_tempo() {
+        let event = |delta: u32, kind| TrackEvent {
+            delta: u28::new(delta),
+            kind,
+        };
+        let note = |channel: u8, key: u8, vel: u8| TrackEventKind::Midi {
+            channel: u4::new(channel),
+            message: MidiMessage::NoteOn {
+                key: u7::new(key),
+                vel: u7::new(vel),
+            },
+        };
+        let tempo = |tempo: u32| TrackEventKind::Meta(MetaMessage::Tempo(u24::new(tempo)));
+        let end = TrackEventKind::Meta(MetaMessage::EndOfTrack);
+        let smf = Smf {
+            header: Header::new(Format::Parallel, Timing::Metrical(u15::new(100))),
+            tracks: vec![
+                vec![event(200, tempo(250_000)), event(0, end)],
+                vec![
+                    event(0, note(0, 60, 100)),
+                    event(0, note(9, 36, 100)),
+                    event(100, note(0, 60, 0)),
+                    event(100, note(1, 64, 50)),
+                    event(200, note(1, 64, 0)),
+                    event(0, note(1, 67, 50)),
+                    event(0, end),
+                ],
+            ],
+        };
+        let mut bytes = Vec::new();
+        smf.write_std(&mut bytes).unwrap();
+
+        let file = MidiFile::parse(&bytes).unwrap();
+        let notes: Vec<_> = file
+            .notes()
+            .iter()
+            .map(|note| (note.onset, note.end, note.channel, note.key, note.velocity))
+            .collect();
+        assert_eq!(notes, [(0., 0.5, 0, 60, 100), (1., 1.5, 1, 64, 50)]);
+        assert_eq!(file.duration(), 1.5);
+    }
+
+    #[test]
+    fn written_notes_keep_bend() {
+        let note = |onset, end, key, bend| MidiNote {
+            onset,
+            end,
+            channel: 0,
+            key,
+            velocity: 90,
+            bend,
+        };
+        let notes = [
+            note(0., 1., 60, 0.),
+            note(0., 1., 64, -13.7),
+            note(0.5, 2., 67, 31.2),
+        ];
+        let file = MidiFile::parse(&MidiFile::new(notes.to_vec()).to_bytes()).unwrap();
+        assert_eq!(file.notes().len(), notes.len());
+        for (parsed, note) in file.notes().iter().zip(notes) {
+            assert_eq!((parsed.key, parsed.velocity), (note.key, note.velocity));
+            assert!((parsed.onset - note.onset).abs() < 1e-3);
+            assert!((parsed.end - note.end).abs() < 1e-3);
+            assert!((parsed.bend - note.bend).abs() < 0.1, "{parsed:?}");
+        }
+    }
+
+    #[test]
+    fn rendered_notes_keep_bend() {
+        use crate::parameters::{
+            oscillators::OscillatorTypeDistribution, DataParameters, OctaveParameters, WaveForms,
+        };
+
+        let data_parameters = DataParameters::new(
+            1000,
+            (100., 200.),
+            (0., 0.),
+            [0],
+            OctaveParameters::new(0., 0., 20., 400.).unwrap(),
+            WaveForms::new(),
+            1000,
+        )
+        .and_then(|parameters| {
+            parameters.with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.5))
+        })
+        .unwrap();
+        let file = MidiFile::new(vec![MidiNote {
+            onset: 0.,
+            end: 0.5,
+            channel: 0,
+            key: 69,
+            velocity: 127,
+            bend: 50.,
+        }]);
+        let events = file.render(&data_parameters, 0).unwrap().events;
+        let expected = crate::A4_FREQUENCY * (50f32 / 1200.).exp2();
+        assert!((events[0].parameters.base_frequency - expected).abs() < 1e-2);
+        assert_eq!(events[0].parameters.note, Some((69, 50.)));
+    }
+
+    #[test]
+    fn scala_frequencies_are_written_as_equal_tempered_keys() {
+        use crate::{
+            parameters::{
+                oscillators::OscillatorTypeDistribution, DataParameters, DataPointParameters,
+                OctaveParameters, WaveForms,
+            },
+            scala::{KeyboardMapping, ScalaTuning, Scale},
+            tuning::Tuning,
+        };
+
+        let scale = Scale::parse("Pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n").unwrap();
+        let tuning = ScalaTuning::new(scale, KeyboardMapping::linear(60, 69, 440.)).unwrap();
+        let data_parameters = DataParameters::new(
+            1000,
+            (100., 1000.),
+            (0., 0.),
+            [0, 1],
+            OctaveParameters::new(0., 0., 20., 4000.).unwrap(),
+            WaveForms::new(),
+            1000,
+        )
+        .and_then(|parameters| {
+            parameters.with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.5))
+        })
+        .unwrap()
+        .with_tuning(Tuning::Scala(tuning));
+        for seed in 0..10 {
+            let parameters = DataPointParameters::new(&data_parameters, seed);
+            let notes: Vec<_> = MidiFile::parameter_notes(&parameters, 0., 1.).collect();
+            assert_eq!(notes.len(), parameters.frequencies.len());
+            for (note, &frequency) in notes.iter().zip(parameters.frequencies.iter()) {
+                let written = crate::note_number_to_frequency_with_reference(
+                    note.key as f32 + note.bend / 100.,
+                    parameters.a4_frequency,
+                );
+                assert!(note.bend.abs() <= 50.);
+                assert!(
+                    crate::cent_diff(written, frequency).abs() < 0.1,
+                    "{note:?} {frequency}"
+                );
+            }
+        }
+    }
+}
@@ -1,22 +1,34 @@
-use std::{fs, path::Path, sync::Arc};
+use std::{fmt, fs, path::Path, sync::Arc};
 
 use rand::{distributions::Standard, prelude::Distribution, seq::SliceRandom, Rng, SeedableRng};
 use rand_pcg::Pcg64Mcg;
 use serde::{Deserialize, Serialize};
 
+use super::effects::{EffectDistribution, EffectParameters, EffectTypeDistribution};
 use crate::{
     audio::AudioGenerationError,
+    chord::{ChordRegistry, ChordType},
+    chord_symbol,
+    config::{self, ConfigError, Violations, WaveFormSource},
     data::DataPoint,
     hash,
-    log_uniform::LogUniform,
-    parameters::oscillators::{
-        OscillatorDistribution, OscillatorParameters, OscillatorTypeDistribution,
+    parameters::{
+        drift::{DriftDistribution, DriftModelDistribution, DriftParameters},
+        modulation::{LfoDistribution, LfoParameters},
+        notes::NoteDistribution,
+        oscillators::{
+            OscillatorDistribution, OscillatorParameters, OscillatorTypeDistribution,
+            UnisonDistribution,
+        },
+        sampler::SampledInstrument,
+        voicing::{self, VoicingDistribution, VoicingParameters},
+        wavetable::Wavetable,
     },
-    Audio, UniformF,
+    theory::ProgressionChord,
+    tuning::Tuning,
+    wave_form_extraction, Audio, UniformF,
 };
 
-use super::effects::{EffectDistribution, EffectParameters, EffectTypeDistribution};
-
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct OctaveParameters {
     add_root_octave_probability: f64,
@@ -31,40 +43,40 @@ impl OctaveParameters {
         add_other_octave_probability: f64,
         min_frequency: f32,
         max_frequency: f32,
-    ) -> Self {
-        assert!(
-            add_root_octave_probability >= 0.,
-            "Probabilities must be non-negative. Value: {add_root_octave_probability}"
-        );
-        assert!(
-            add_root_octave_probability <= 1.,
-            "Probabilities must be no more than 1. Value: {add_root_octave_probability}"
-        );
-        assert!(
-            add_other_octave_probability >= 0.,
-            "Probabilities must be non-negative. Value: {add_other_octave_probability}"
-        );
-        assert!(
-            add_other_octave_probability <= 1.,
-            "Probabilities must be no more than 1. Value: {add_other_octave_probability}"
-        );
-
-        assert!(
-            min_frequency > 0.,
-            "Minimum frequency must be positive. Value: {min_frequency}"
-        );
-
-        assert!(
-            max_frequency >= min_frequency*2.,
-            "Maximum frequency must be at least twice the minimum frequency. Max frequency: {max_frequency}"
-        );
-
-        Self {
+    ) -> Result<Self, ConfigError> {
+        let octave_parameters = Self {
             add_root_octave_probability,
             add_other_octave_probability,
             min_frequency,
             max_frequency,
-        }
+        };
+        octave_parameters.validate()?;
+        Ok(octave_parameters)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check_probability(
+            "add_root_octave_probability",
+            self.add_root_octave_probability,
+            true,
+        );
+        violations.check_probability(
+            "add_other_octave_probability",
+            self.add_other_octave_probability,
+            true,
+        );
+        violations.check(self.min_frequency > 0., || {
+            ConfigError::Zero("Minimum octave frequency")
+        });
+        violations.check(self.max_frequency >= self.min_frequency * 2., || {
+            ConfigError::InvalidRange {
+                name: "octave frequency",
+                range: (self.min_frequency as f64, self.max_frequency as f64),
+                requirement: "the maximum must be at least twice the minimum",
+            }
+        });
+        violations.into_result()
     }
 
     pub fn generate_octave(&self, rng: &mut impl Rng, frequency: f32) -> f32 {
@@ -77,6 +89,17 @@ impl OctaveParameters {
         frequency * (octave as f32).exp2()
     }
 
+    /// Moves the frequency by whole octaves until it lies within the frequency range.
+    pub fn fold_into_range(&self, frequency: f32) -> f32 {
+        if frequency < self.min_frequency {
+            frequency * (self.min_frequency / frequency).log2().ceil().exp2()
+        } else if frequency > self.max_frequency {
+            frequency / (frequency / self.max_frequency).log2().ceil().exp2()
+        } else {
+            frequency
+        }
+    }
+
     pub fn generate_frequencies(
         &self,
         rng: &mut impl Rng,
@@ -117,52 +140,177 @@ impl OctaveParameters {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WaveForms {
     wave_forms: Vec<Vec<f32>>,
+    #[serde(default)]
+    wavetables: Vec<Wavetable>,
+    instruments: Vec<SampledInstrument>,
+    /// Where the wave forms, wavetables and instruments were loaded from, in order.
+    #[serde(default)]
+    sources: Vec<WaveFormSource>,
+}
+
+/// Only summarizes the banks, since the samples and mipmaps are far too long to print.
+impl fmt::Debug for WaveForms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WaveForms")
+            .field("wave_forms", &self.wave_forms.len())
+            .field("wavetables", &self.wavetables.len())
+            .field("instruments", &self.instruments.len())
+            .field("sources", &self.sources)
+            .finish()
+    }
 }
 
 impl WaveForms {
     pub fn new() -> Self {
         Self {
             wave_forms: Vec::new(),
+            wavetables: Vec::new(),
+            instruments: Vec::new(),
+            sources: Vec::new(),
         }
     }
 
-    fn add(mut self, wave_form: Vec<f32>) -> Self {
-        assert!(
-            !wave_form.is_empty(),
-            "Wave form must have at least one point."
-        );
-        assert!(
-            wave_form.iter().all(|&x| x.is_finite()),
-            "Wave form must be finite and non-NaN."
-        );
+    /// Loads the given sources in order, with relative paths resolved against `base_path`.
+    pub fn from_sources<P>(sources: &[WaveFormSource], base_path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        sources.iter().try_fold(Self::new(), |wave_forms, source| {
+            wave_forms.add_source(source.clone(), base_path.as_ref())
+        })
+    }
+
+    /// Where the wave forms, wavetables and instruments were loaded from, in order.
+    pub fn sources(&self) -> &[WaveFormSource] {
+        &self.sources
+    }
+
+    fn add_source(mut self, source: WaveFormSource, base_path: &Path) -> Result<Self, ConfigError> {
+        // Takes paths that are already resolved against `base_path`.
+        let load = |path: &Path| {
+            Audio::from_wav(path).map_err(|error| ConfigError::InvalidAudio {
+                path: path.to_owned(),
+                message: error.to_string(),
+            })
+        };
+        match &source {
+            WaveFormSource::File(path) => {
+                let path = base_path.join(path);
+                let wave_form = load(&path)?.samples;
+                self.add(wave_form, &path)?;
+            }
+            WaveFormSource::Directory(path) => {
+                let path = base_path.join(path);
+                let io_error = |error| ConfigError::Io {
+                    path: path.clone(),
+                    error,
+                };
+                let mut file_paths = fs::read_dir(&path)
+                    .map_err(io_error)?
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(io_error)?;
+                file_paths.retain(|path| path.extension().map(|ext| ext == "wav").unwrap_or(false));
+                file_paths.sort();
+                for file_path in file_paths {
+                    let wave_form = load(&file_path)?.samples;
+                    self.add(wave_form, &file_path)?;
+                }
+            }
+            WaveFormSource::Extracted { path, num_periods } => {
+                if *num_periods == 0 {
+                    return Err(ConfigError::Zero("num_periods"));
+                }
+                let path = base_path.join(path);
+                let wave_form =
+                    wave_form_extraction::extract_wave_form(&load(&path)?, *num_periods).map_err(
+                        |error| ConfigError::InvalidAudio {
+                            path: path.clone(),
+                            message: error.to_string(),
+                        },
+                    )?;
+                self.add(wave_form, &path)?;
+            }
+            WaveFormSource::Instrument(path) => {
+                let path = base_path.join(path);
+                let instrument = SampledInstrument::load_dir(&path).map_err(|error| {
+                    ConfigError::InvalidAudio {
+                        path,
+                        message: format!("{error:#}"),
+                    }
+                })?;
+                self.instruments.push(instrument);
+            }
+            WaveFormSource::Wavetable(indices) => {
+                if indices.len() < 2 || indices.iter().any(|&index| index >= self.wave_forms.len())
+                {
+                    return Err(ConfigError::InvalidWavetable {
+                        indices: indices.clone(),
+                        num_wave_forms: self.wave_forms.len(),
+                    });
+                }
+                let wave_forms: Vec<_> = indices.iter().map(|&index| self.get(index)).collect();
+                let wavetable = Wavetable::new(&wave_forms);
+                self.wavetables.push(wavetable);
+            }
+        }
+        self.sources.push(source);
+        Ok(self)
+    }
+
+    fn add(&mut self, wave_form: Vec<f32>, path: &Path) -> Result<(), ConfigError> {
+        let invalid = |message: &str| ConfigError::InvalidAudio {
+            path: path.to_owned(),
+            message: message.to_owned(),
+        };
+        if wave_form.is_empty() {
+            return Err(invalid("Wave form must have at least one point."));
+        }
+        if !wave_form.iter().all(|&x| x.is_finite()) {
+            return Err(invalid("Wave form must be finite and non-NaN."));
+        }
         self.wave_forms.push(wave_form);
-        self
+        Ok(())
     }
 
-    pub fn load_and_add<P>(self, path: P) -> Self
+    pub fn load_and_add<P>(self, path: P) -> Result<Self, ConfigError>
     where
         P: AsRef<Path>,
     {
-        let audio = Audio::from_wav(path).expect("Could not load audio file.");
-        let wave_form = audio.samples;
-        self.add(wave_form)
+        self.add_source(
+            WaveFormSource::File(path.as_ref().to_owned()),
+            Path::new(""),
+        )
     }
 
-    pub fn load_dir_and_add<P>(mut self, path: P) -> Self
+    /// Extracts `num_periods` periods from a recording of a sustained note and adds them as a wave form.
+    ///
+    /// See [`wave_form_extraction::extract_wave_form`] for how the periods are chosen.
+    pub fn extract_and_add<P>(self, path: P, num_periods: usize) -> Result<Self, ConfigError>
     where
         P: AsRef<Path>,
     {
-        for entry in fs::read_dir(path).expect("Could not read directory.") {
-            let entry = entry.expect("Could not read directory entry.");
-            let path = entry.path();
-            if path.extension().map(|ext| ext == "wav").unwrap_or(false) {
-                self = self.load_and_add(path);
-            }
-        }
-        self
+        self.add_source(
+            WaveFormSource::Extracted {
+                path: path.as_ref().to_owned(),
+                num_periods,
+            },
+            Path::new(""),
+        )
+    }
+
+    /// Adds every WAV file in a directory as a wave form, in order of file name.
+    pub fn load_dir_and_add<P>(self, path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        self.add_source(
+            WaveFormSource::Directory(path.as_ref().to_owned()),
+            Path::new(""),
+        )
     }
 
     pub fn get(&self, index: usize) -> &[f32] {
@@ -172,6 +320,44 @@ impl WaveForms {
     pub fn num_wave_forms(&self) -> usize {
         self.wave_forms.len()
     }
+
+    /// Adds a wavetable morphing between the wave forms with the given indices in order.
+    pub fn with_wavetable<A>(self, wave_form_indices: A) -> Result<Self, ConfigError>
+    where
+        A: AsRef<[usize]>,
+    {
+        self.add_source(
+            WaveFormSource::Wavetable(wave_form_indices.as_ref().to_vec()),
+            Path::new(""),
+        )
+    }
+
+    pub fn wavetable(&self, index: usize) -> &Wavetable {
+        &self.wavetables[index]
+    }
+
+    pub fn num_wavetables(&self) -> usize {
+        self.wavetables.len()
+    }
+
+    /// Loads a directory of recorded notes as an instrument. See [`SampledInstrument::load_dir`].
+    pub fn load_instrument_and_add<P>(self, path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        self.add_source(
+            WaveFormSource::Instrument(path.as_ref().to_owned()),
+            Path::new(""),
+        )
+    }
+
+    pub fn instrument(&self, index: usize) -> &SampledInstrument {
+        &self.instruments[index]
+    }
+
+    pub fn num_instruments(&self) -> usize {
+        self.instruments.len()
+    }
 }
 
 impl Default for WaveForms {
@@ -180,18 +366,32 @@ impl Default for WaveForms {
     }
 }
 
+/// Serialized as its [`DataConfig`], so wave forms are referenced by their sources. Deserializing resolves the
+/// config relative to the working directory and validates it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "DataConfig", into = "DataConfig")]
 pub struct DataParameters {
     sample_rate: u32,
     frequency_distribution: UniformF,
-    frequency_std_dev_distribution: LogUniform,
+    note_distribution: Option<NoteDistribution>,
+    concert_pitch_distribution: UniformF,
+    drift_distribution: DriftDistribution,
+    chords: ChordRegistry,
     possible_chords: Vec<u32>,
+    tuning: Tuning,
+    voicing: Option<VoicingDistribution>,
     octave_parameters: OctaveParameters,
     oscillators: Vec<OscillatorDistribution>,
     effects: Vec<EffectDistribution>,
+    vibrato: Option<LfoDistribution>,
+    tremolo: Option<LfoDistribution>,
     wave_forms: Arc<WaveForms>,
     num_samples: u64,
-    seed_offset: u64,
+    seed: u64,
+}
+
+fn default_concert_pitch_distribution() -> UniformF {
+    UniformF::new(crate::A4_FREQUENCY, crate::A4_FREQUENCY)
 }
 
 impl DataParameters {
@@ -203,105 +403,503 @@ impl DataParameters {
         octave_parameters: OctaveParameters,
         wave_forms: WaveForms,
         num_samples: u64,
-    ) -> Self
+    ) -> Result<Self, ConfigError>
     where
         A: AsRef<[u32]>,
     {
-        assert!(
-            frequency_range.0 < frequency_range.1,
-            "Invalid frequency range."
-        );
-        assert!(num_samples > 0, "num_samples must be greater than 0.");
-        assert!(sample_rate > 0, "sample_rate must be greater than 0.");
-        assert!(
-            frequency_std_dev_range.0 >= 0.0,
-            "frequency_std_dev_range must be non-negative."
-        );
-        assert!(
-            frequency_std_dev_range.1 >= frequency_std_dev_range.0,
-            "frequency_std_dev_range maximum must be no less than the minimum."
-        );
         let possible_chords: Vec<u32> = possible_chords.as_ref().to_vec();
-        assert!(!possible_chords.is_empty(), "No chords provided.");
+        let chords = ChordRegistry::default();
 
+        let mut violations = Violations::default();
+        violations.check(
+            0. < frequency_range.0 && frequency_range.0 < frequency_range.1,
+            || ConfigError::InvalidRange {
+                name: "frequency",
+                range: (frequency_range.0 as f64, frequency_range.1 as f64),
+                requirement: "the minimum must be positive and less than the maximum",
+            },
+        );
+        violations.check(
+            0. <= frequency_std_dev_range.0
+                && frequency_std_dev_range.0 <= frequency_std_dev_range.1,
+            || ConfigError::InvalidRange {
+                name: "frequency standard deviation",
+                range: (
+                    frequency_std_dev_range.0 as f64,
+                    frequency_std_dev_range.1 as f64,
+                ),
+                requirement: "the minimum must be non-negative and no greater than the maximum",
+            },
+        );
+        violations.check(sample_rate > 0, || ConfigError::Zero("sample_rate"));
+        violations.check(num_samples > 0, || ConfigError::Zero("num_samples"));
+        violations.check(!possible_chords.is_empty(), || ConfigError::NoChords);
         for &chord_type in possible_chords.iter() {
-            assert!(
-                chord_type < crate::CHORD_TYPES.len() as u32,
-                "Invalid chord type. Chord type must be less than {}.",
-                crate::CHORD_TYPES.len()
-            );
+            violations.check(chord_type < chords.len() as u32, || {
+                ConfigError::InvalidChord(chord_type)
+            });
         }
-
         let min_frequency_map = crate::frequency_to_map(frequency_range.0);
         let max_frequency_map = crate::frequency_to_map(frequency_range.1);
-        Self {
+        violations.check_range(
+            "frequency map",
+            (min_frequency_map, max_frequency_map),
+            (-1., 1.),
+            "the frequency range must lie within the range of the frequency map",
+        );
+        violations.into_result()?;
+
+        Ok(Self {
             sample_rate,
             frequency_distribution: UniformF::new(min_frequency_map, max_frequency_map),
-            frequency_std_dev_distribution: LogUniform::from_tuple(frequency_std_dev_range),
+            note_distribution: None,
+            concert_pitch_distribution: default_concert_pitch_distribution(),
+            drift_distribution: DriftDistribution::new(DriftModelDistribution::random_walk(
+                frequency_std_dev_range,
+                (0.9, 0.9),
+            )),
+            chords,
             possible_chords,
+            tuning: Tuning::default(),
+            voicing: None,
             octave_parameters,
             wave_forms: Arc::new(wave_forms),
             oscillators: vec![],
             effects: vec![],
+            vibrato: None,
+            tremolo: None,
             num_samples,
-            seed_offset: hash(hash(0)),
-        }
+            seed: 0,
+        })
     }
 
     pub fn num_wave_forms(&self) -> usize {
         self.wave_forms.num_wave_forms()
     }
 
+    /// Registers a new chord type. It is not generated unless it is added to the possible chords.
+    pub fn with_chord<S>(mut self, name: S, chord_type: ChordType) -> Result<Self, ConfigError>
+    where
+        S: Into<String>,
+    {
+        self.chords = self.chords.with_chord(name, chord_type)?;
+        Ok(self)
+    }
+
+    /// Replaces the possible chords with the registered chords of the given names.
+    ///
+    /// A name can also be a chord quality suffix such as `m7` or `maj9`, which selects the registered chord with
+    /// the same intervals.
+    pub fn with_possible_chords<S>(mut self, names: &[S]) -> Result<Self, ConfigError>
+    where
+        S: AsRef<str>,
+    {
+        if names.is_empty() {
+            return Err(ConfigError::NoChords);
+        }
+        self.possible_chords = names
+            .iter()
+            .map(|name| {
+                let name = name.as_ref();
+                self.chords
+                    .index(name)
+                    .or_else(|| {
+                        let intervals = chord_symbol::quality_intervals(name)?;
+                        self.chords
+                            .index_of(&ChordType::from_valid_intervals(intervals))
+                    })
+                    .ok_or_else(|| ConfigError::UnknownChord(name.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    pub fn chords(&self) -> &ChordRegistry {
+        &self.chords
+    }
+
+    /// Sets how chord intervals are tuned relative to the root. Defaults to just intonation.
+    ///
+    /// With a [`Tuning::Scala`] tuning, base frequencies are also snapped to the keys of its keyboard mapping.
+    pub fn with_tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    pub fn tuning(&self) -> &Tuning {
+        &self.tuning
+    }
+
+    /// Voices chords explicitly instead of placing every note in a random octave.
+    ///
+    /// The voicing is built around the base frequency. The root is still doubled in other octaves with the
+    /// probability of the octave parameters, but the other notes are not spread over octaves.
+    pub fn with_voicing(mut self, voicing_distribution: VoicingDistribution) -> Self {
+        self.voicing = Some(voicing_distribution);
+        self
+    }
+
+    /// Samples base frequencies on the note grid of the tuning instead of continuously from the frequency range.
+    pub fn with_note_distribution(mut self, note_distribution: NoteDistribution) -> Self {
+        self.note_distribution = Some(note_distribution);
+        self
+    }
+
+    /// Samples the frequency of A4 for every data point from `a4_frequency_range` and shifts all notes by the
+    /// difference to [`crate::A4_FREQUENCY`]. Defaults to a fixed concert pitch of 440 Hz.
+    pub fn with_concert_pitch(
+        mut self,
+        a4_frequency_range: (f32, f32),
+    ) -> Result<Self, ConfigError> {
+        let mut violations = Violations::default();
+        violations.check_range(
+            "concert pitch",
+            a4_frequency_range,
+            (f32::MIN_POSITIVE, f32::MAX),
+            "the range must be positive and non-empty",
+        );
+        violations.into_result()?;
+        self.concert_pitch_distribution = UniformF::new(a4_frequency_range.0, a4_frequency_range.1);
+        Ok(self)
+    }
+
     pub fn with_seed_offset(mut self, seed_offset: u64) -> Self {
-        self.seed_offset = hash(hash(seed_offset));
+        self.seed = seed_offset;
         self
     }
 
     pub fn with_oscillator(
-        mut self,
+        self,
         oscillator_type_distribution: OscillatorTypeDistribution,
         probability: f64,
         amplitude_range: (f32, f32),
-    ) -> Self {
-        self.oscillators.push(OscillatorDistribution::new(
+    ) -> Result<Self, ConfigError> {
+        self.add_oscillator(OscillatorDistribution::new(
             oscillator_type_distribution,
             probability,
             amplitude_range,
-        ));
-        let osc_amplitude_sum = self
-            .oscillators
-            .iter()
-            .map(|oscillator_distr| oscillator_distr.maximum_amplitude())
-            .sum::<f32>();
-        if osc_amplitude_sum > 1. {
-            panic!(
-                "The sum of oscillator amplitudes must not exceed 1. Current: {osc_amplitude_sum}"
-            );
+        )?)
+    }
+
+    /// Adds an oscillator that is rendered as a stack of detuned voices. The amplitude is shared between the voices.
+    pub fn with_unison_oscillator(
+        self,
+        oscillator_type_distribution: OscillatorTypeDistribution,
+        probability: f64,
+        amplitude_range: (f32, f32),
+        unison_distribution: UnisonDistribution,
+    ) -> Result<Self, ConfigError> {
+        self.add_oscillator(
+            OscillatorDistribution::new(
+                oscillator_type_distribution,
+                probability,
+                amplitude_range,
+            )?
+            .with_unison(unison_distribution)?,
+        )
+    }
+
+    fn add_oscillator(
+        mut self,
+        oscillator_distribution: OscillatorDistribution,
+    ) -> Result<Self, ConfigError> {
+        self.oscillators.push(oscillator_distribution);
+        let amplitude_sum = self.amplitude_sum();
+        if amplitude_sum > 1. {
+            return Err(ConfigError::AmplitudeSum(amplitude_sum));
         }
-        self
+        Ok(self)
+    }
+
+    /// Whether any oscillator has a frequency, without which no data point can be sampled.
+    pub(crate) fn has_pitched_oscillator(&self) -> bool {
+        self.oscillators
+            .iter()
+            .any(|oscillator_distribution| oscillator_distribution.has_frequency())
+    }
+
+    fn amplitude_sum(&self) -> f32 {
+        self.oscillators
+            .iter()
+            .map(|oscillator_distribution| oscillator_distribution.maximum_amplitude())
+            .sum()
     }
 
     pub fn with_effect(
         mut self,
         effect_distribution: EffectTypeDistribution,
         probability: f64,
-    ) -> Self {
+    ) -> Result<Self, ConfigError> {
         self.effects
-            .push(EffectDistribution::new(effect_distribution, probability));
+            .push(EffectDistribution::new(effect_distribution, probability)?);
+        Ok(self)
+    }
+
+    /// Replaces the pitch drift model, which defaults to an independent random walk for every note and oscillator.
+    pub fn with_drift(mut self, drift_distribution: DriftDistribution) -> Self {
+        self.drift_distribution = drift_distribution;
         self
     }
 
+    /// Adds vibrato with the given probability. `depth_range` is the peak pitch deviation in cents.
+    pub fn with_vibrato(
+        mut self,
+        rate_range: (f32, f32),
+        depth_range: (f32, f32),
+        delay_range: (f32, f32),
+        probability: f64,
+    ) -> Result<Self, ConfigError> {
+        let vibrato = LfoDistribution::from_unchecked_ranges(
+            rate_range,
+            depth_range,
+            delay_range,
+            probability,
+        );
+        vibrato.validate("vibrato")?;
+        self.vibrato = Some(vibrato);
+        Ok(self)
+    }
+
+    /// Adds tremolo with the given probability. `depth_range` is the peak attenuation in decibels.
+    pub fn with_tremolo(
+        mut self,
+        rate_range: (f32, f32),
+        depth_range: (f32, f32),
+        delay_range: (f32, f32),
+        probability: f64,
+    ) -> Result<Self, ConfigError> {
+        let tremolo = LfoDistribution::from_unchecked_ranges(
+            rate_range,
+            depth_range,
+            delay_range,
+            probability,
+        );
+        tremolo.validate("tremolo")?;
+        self.tremolo = Some(tremolo);
+        Ok(self)
+    }
+
     pub fn num_samples(&self) -> u64 {
         self.num_samples
     }
 
     pub fn generate(&self, index: u64) -> DataPointParameters {
-        assert!(
-            self.oscillators.iter().any(|osc| osc.has_frequency()),
-            "Cannot generate a signal without an oscillator with frequency."
+        DataPointParameters::new(self, self.seed(index))
+    }
+
+    /// Index of a chord type taken from a progression, which must be registered and among the possible chords.
+    pub(crate) fn progression_chord_type(
+        &self,
+        chord_type: &ChordType,
+    ) -> Result<u32, ConfigError> {
+        self.chords
+            .index_of(chord_type)
+            .filter(|index| self.possible_chords.contains(index))
+            .ok_or_else(|| ConfigError::UnavailableChord(chord_type.intervals().to_vec()))
+    }
+
+    /// Seed of the data point with the given index.
+    pub(crate) fn seed(&self, index: u64) -> u64 {
+        hash(index).wrapping_add(hash(hash(self.seed)))
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Loads a config with [`DataConfig::load`] and resolves it relative to the directory of the config file.
+    pub fn load_config<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        DataConfig::load(path)?.resolve(path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// The config these parameters can be loaded from again, with wave forms referenced by their sources.
+    pub fn config(&self) -> DataConfig {
+        DataConfig {
+            sample_rate: self.sample_rate,
+            frequency_distribution: self.frequency_distribution,
+            note_distribution: self.note_distribution.clone(),
+            concert_pitch_distribution: self.concert_pitch_distribution,
+            drift_distribution: self.drift_distribution.clone(),
+            chords: self.chords.clone(),
+            possible_chords: self.possible_chords.clone(),
+            tuning: self.tuning.clone(),
+            voicing: self.voicing.clone(),
+            octave_parameters: self.octave_parameters,
+            oscillators: self.oscillators.clone(),
+            effects: self.effects.clone(),
+            vibrato: self.vibrato.clone(),
+            tremolo: self.tremolo.clone(),
+            wave_forms: self.wave_forms.sources().to_vec(),
+            num_samples: self.num_samples,
+            seed: self.seed,
+        }
+    }
+
+    /// Checks what the constructors check, for parameters that were deserialized instead of built. Every
+    /// violated constraint is reported, not only the first.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(self.sample_rate > 0, || ConfigError::Zero("sample_rate"));
+        violations.check(self.num_samples > 0, || ConfigError::Zero("num_samples"));
+        violations.check(!self.possible_chords.is_empty(), || ConfigError::NoChords);
+        for &chord_type in self.possible_chords.iter() {
+            violations.check(chord_type < self.chords.len() as u32, || {
+                ConfigError::InvalidChord(chord_type)
+            });
+        }
+        violations.add(self.octave_parameters.validate());
+        if let Some(note_distribution) = &self.note_distribution {
+            violations.add(note_distribution.validate());
+        }
+        violations.check_range(
+            "frequency map",
+            self.frequency_distribution.range(),
+            (-1., 1.),
+            "the range must be within [-1;1] and non-empty",
         );
-        let seed = hash(index).wrapping_add(self.seed_offset);
-        DataPointParameters::new(self, seed)
+        violations.check_range(
+            "concert pitch",
+            self.concert_pitch_distribution.range(),
+            (f32::MIN_POSITIVE, f32::MAX),
+            "the range must be positive and non-empty",
+        );
+        violations.add(self.drift_distribution.validate());
+        violations.add(self.tuning.validate());
+        if let Some(voicing) = &self.voicing {
+            violations.add(voicing.validate());
+        }
+        if let Some(vibrato) = &self.vibrato {
+            violations.add(vibrato.validate("vibrato"));
+        }
+        if let Some(tremolo) = &self.tremolo {
+            violations.add(tremolo.validate("tremolo"));
+        }
+        for oscillator_distribution in self.oscillators.iter() {
+            violations.add(oscillator_distribution.validate());
+            violations.add(oscillator_distribution.check_banks(&self.wave_forms));
+        }
+        violations.check(self.has_pitched_oscillator(), || {
+            ConfigError::NoPitchedOscillator
+        });
+        let amplitude_sum = self.amplitude_sum();
+        violations.check(amplitude_sum <= 1., || {
+            ConfigError::AmplitudeSum(amplitude_sum)
+        });
+        for effect_distribution in self.effects.iter() {
+            violations.add(effect_distribution.validate());
+        }
+        violations.into_result()
+    }
+}
+
+/// [`DataParameters`] in a form that can be written by hand and kept in version control. Wave forms, wavetables
+/// and instruments are referenced by path instead of embedded, and are loaded when the config is resolved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataConfig {
+    sample_rate: u32,
+    frequency_distribution: UniformF,
+    #[serde(default)]
+    note_distribution: Option<NoteDistribution>,
+    #[serde(default = "default_concert_pitch_distribution")]
+    concert_pitch_distribution: UniformF,
+    drift_distribution: DriftDistribution,
+    #[serde(default)]
+    chords: ChordRegistry,
+    possible_chords: Vec<u32>,
+    #[serde(default)]
+    tuning: Tuning,
+    #[serde(default)]
+    voicing: Option<VoicingDistribution>,
+    octave_parameters: OctaveParameters,
+    oscillators: Vec<OscillatorDistribution>,
+    #[serde(default)]
+    effects: Vec<EffectDistribution>,
+    #[serde(default)]
+    vibrato: Option<LfoDistribution>,
+    #[serde(default)]
+    tremolo: Option<LfoDistribution>,
+    #[serde(default)]
+    wave_forms: Vec<WaveFormSource>,
+    num_samples: u64,
+    #[serde(default)]
+    seed: u64,
+}
+
+impl DataConfig {
+    /// Reads a config from a TOML file if the extension is `.toml` and from a JSON file otherwise.
+    pub fn load<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        config::read(path.as_ref())
+    }
+
+    /// Writes the config as TOML if the extension is `.toml` and as JSON otherwise.
+    pub fn save<P>(&self, path: P) -> Result<(), ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let syntax_error = |message: String| ConfigError::Syntax {
+            path: path.to_owned(),
+            message,
+        };
+        let text = if config::is_toml(path) {
+            toml::to_string_pretty(self).map_err(|error| syntax_error(error.to_string()))?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|error| syntax_error(error.to_string()))?
+        };
+        fs::write(path, text).map_err(|error| ConfigError::Io {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Loads the referenced files, with relative paths resolved against `base_path`, and validates the result.
+    pub fn resolve<P>(self, base_path: P) -> Result<DataParameters, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let wave_forms = WaveForms::from_sources(&self.wave_forms, base_path)?;
+        let parameters = DataParameters {
+            sample_rate: self.sample_rate,
+            frequency_distribution: self.frequency_distribution,
+            note_distribution: self.note_distribution,
+            concert_pitch_distribution: self.concert_pitch_distribution,
+            drift_distribution: self.drift_distribution,
+            chords: self.chords,
+            possible_chords: self.possible_chords,
+            tuning: self.tuning,
+            voicing: self.voicing,
+            octave_parameters: self.octave_parameters,
+            oscillators: self.oscillators,
+            effects: self.effects,
+            vibrato: self.vibrato,
+            tremolo: self.tremolo,
+            wave_forms: Arc::new(wave_forms),
+            num_samples: self.num_samples,
+            seed: self.seed,
+        };
+        parameters.validate()?;
+        Ok(parameters)
+    }
+}
+
+impl TryFrom<DataConfig> for DataParameters {
+    type Error = ConfigError;
+
+    fn try_from(config: DataConfig) -> Result<Self, Self::Error> {
+        config.resolve("")
+    }
+}
+
+impl From<DataParameters> for DataConfig {
+    fn from(parameters: DataParameters) -> Self {
+        parameters.config()
     }
 }
 
@@ -309,21 +907,111 @@ impl DataParameters {
 pub struct DataPointParameters {
     pub sample_rate: u32,
     pub base_frequency: f32,
-    pub frequency_std_dev: f32,
+    /// Frequency of A4 the notes are tuned to.
+    pub a4_frequency: f32,
+    /// MIDI note number and detune in cents of the base frequency if it was sampled on the note grid.
+    pub note: Option<(u32, f32)>,
+    /// The chord of a progression this data point plays, if it is part of a sequence driven by a progression.
+    pub progression_chord: Option<ProgressionChord>,
+    pub drift: DriftParameters,
     pub frequency_walk_seed: u64,
     pub chord_type: u32,
+    pub chord_name: String,
+    pub chord: ChordType,
+    pub tuning: Tuning,
+    pub voicing: Option<VoicingParameters>,
+    /// Index of the lowest note of the chord if it was voiced explicitly.
+    pub inversion: Option<usize>,
     pub frequencies: Vec<f32>,
     pub oscillators: Vec<OscillatorParameters>,
     pub effects: Vec<EffectParameters>,
+    pub vibrato: Option<LfoParameters>,
+    pub tremolo: Option<LfoParameters>,
     pub wave_forms: Arc<WaveForms>,
     pub num_samples: u64,
 }
 
+/// Moves a note number by octaves into the MIDI range, keeping its pitch class.
+/// Frequencies of the notes of a voicing. The root on the base frequency is kept, and every other note outside the
+/// frequency range is folded back into it by octaves.
+fn voiced_frequencies(
+    positions: &[i32],
+    base_frequency: f32,
+    tuning: &Tuning,
+    octave_parameters: &OctaveParameters,
+) -> Vec<f32> {
+    positions
+        .iter()
+        .map(|&position| match position {
+            0 => base_frequency,
+            _ => octave_parameters.fold_into_range(base_frequency * tuning.ratio(position)),
+        })
+        .collect()
+}
+
+fn clamp_to_midi_range(note_number: i32) -> u32 {
+    let pitch_class = note_number.rem_euclid(12);
+    note_number.clamp(pitch_class, pitch_class + 12 * ((127 - pitch_class) / 12)) as u32
+}
+
 impl DataPointParameters {
-    fn new(data_parameters: &DataParameters, seed: u64) -> Self {
+    pub(crate) fn new(data_parameters: &DataParameters, seed: u64) -> Self {
+        Self::sample(data_parameters, seed, None)
+    }
+
+    /// Like [`DataPointParameters::new`], but with the root and chord type taken from a chord of a progression
+    /// instead of sampled. The base frequency is moved to the nearest note with the pitch class of the root.
+    ///
+    /// Fails if the chord type is not registered or not among the possible chords.
+    pub(crate) fn with_progression_chord(
+        data_parameters: &DataParameters,
+        seed: u64,
+        progression_chord: Option<ProgressionChord>,
+    ) -> Result<Self, ConfigError> {
+        let progression_chord = match progression_chord {
+            Some(progression_chord) => {
+                let chord_type =
+                    data_parameters.progression_chord_type(&progression_chord.chord_type)?;
+                Some((progression_chord, chord_type))
+            }
+            None => None,
+        };
+        Ok(Self::sample(data_parameters, seed, progression_chord))
+    }
+
+    /// Samples a data point, with the root taken from the progression chord if there is one. The chord type
+    /// index of the progression chord is already resolved.
+    fn sample(
+        data_parameters: &DataParameters,
+        seed: u64,
+        progression_chord: Option<(ProgressionChord, u32)>,
+    ) -> Self {
+        // Oscillators are resampled until one has a frequency, which never happens without this check.
+        assert!(
+            data_parameters.has_pitched_oscillator(),
+            "Cannot generate a signal without an oscillator with frequency."
+        );
         let mut rng = Pcg64Mcg::seed_from_u64(seed);
-        let frequency_map = data_parameters.frequency_distribution.sample(&mut rng);
-        let base_frequency = crate::map_to_frequency(frequency_map);
+        let (mut note, mut base_frequency) = match &data_parameters.note_distribution {
+            Some(note_distribution) => {
+                let (note_number, detune) = note_distribution.sample(&mut rng);
+                let frequency = data_parameters
+                    .tuning
+                    .note_number_to_frequency(note_number as f32, crate::A4_FREQUENCY)
+                    * (detune / 1200.).exp2();
+                (Some((note_number, detune)), frequency)
+            }
+            None => {
+                let frequency_map = data_parameters.frequency_distribution.sample(&mut rng);
+                let frequency = crate::map_to_frequency(frequency_map);
+                match &data_parameters.tuning {
+                    Tuning::Scala(scala) => (None, scala.nearest_key_frequency(frequency)),
+                    _ => (None, frequency),
+                }
+            }
+        };
+        let a4_frequency = data_parameters.concert_pitch_distribution.sample(&mut rng);
+        base_frequency *= a4_frequency / crate::A4_FREQUENCY;
 
         let oscillators = loop {
             let oscillators: Vec<_> = data_parameters
@@ -339,32 +1027,84 @@ impl DataPointParameters {
             }
         };
 
-        let chord_type = *data_parameters.possible_chords.choose(&mut rng).unwrap();
+        let chord_type = match &progression_chord {
+            Some((progression_chord, chord_type)) => {
+                let tuning = &data_parameters.tuning;
+                let note_number = tuning
+                    .frequency_to_note_number(base_frequency, a4_frequency)
+                    .round() as i32;
+                let offset = (progression_chord.root as i32 - note_number + 6).rem_euclid(12) - 6;
+                let note_number = clamp_to_midi_range(note_number + offset);
+                let detune = note.map(|(_, detune)| detune).unwrap_or(0.);
+                base_frequency = tuning.note_number_to_frequency(note_number as f32, a4_frequency)
+                    * (detune / 1200.).exp2();
+                note = note.map(|(_, detune)| (note_number, detune));
+                *chord_type
+            }
+            None => *data_parameters.possible_chords.choose(&mut rng).unwrap(),
+        };
+        let progression_chord = progression_chord.map(|(progression_chord, _)| progression_chord);
 
-        let chord = crate::CHORD_TYPES[chord_type as usize].1;
+        let chord = data_parameters.chords.get(chord_type).clone();
 
         let octave_parameters = &data_parameters.octave_parameters;
 
-        let frequencies: Vec<f32> = octave_parameters
-            .generate_frequencies(&mut rng, base_frequency, true)
-            .chain(
-                chord
-                    .frequencies(base_frequency)
-                    .skip(1)
-                    .flat_map(|frequency| {
-                        octave_parameters.generate_frequencies(&mut rng, frequency, false)
-                    }),
-            )
-            .collect();
+        let voicing = data_parameters
+            .voicing
+            .as_ref()
+            .map(|voicing_distribution| voicing_distribution.sample(&mut rng, chord.num_notes()));
+
+        let (frequencies, inversion): (Vec<f32>, _) = match &voicing {
+            Some(voicing) => {
+                let frequencies = voiced_frequencies(
+                    &voicing.positions(&chord),
+                    base_frequency,
+                    &data_parameters.tuning,
+                    octave_parameters,
+                );
+                // Folding can change which note is lowest, so the inversion is taken from the folded frequencies.
+                let inversion = voicing::realized_inversion(&frequencies, chord.num_notes());
+                // The other roots are octave doublings.
+                let frequencies = frequencies
+                    .into_iter()
+                    .chain(
+                        octave_parameters
+                            .generate_frequencies(&mut rng, base_frequency, true)
+                            .skip(1),
+                    )
+                    .collect();
+                (frequencies, Some(inversion))
+            }
+            None => {
+                let frequencies = octave_parameters
+                    .generate_frequencies(&mut rng, base_frequency, true)
+                    .chain(
+                        chord
+                            .frequencies(base_frequency, &data_parameters.tuning)
+                            .skip(1)
+                            .flat_map(|frequency| {
+                                octave_parameters.generate_frequencies(&mut rng, frequency, false)
+                            }),
+                    )
+                    .collect();
+                (frequencies, None)
+            }
+        };
 
         Self {
             sample_rate: data_parameters.sample_rate,
             base_frequency,
-            frequency_std_dev: data_parameters
-                .frequency_std_dev_distribution
-                .sample(&mut rng),
+            a4_frequency,
+            note,
+            progression_chord,
+            drift: data_parameters.drift_distribution.sample(&mut rng),
             frequency_walk_seed: rng.sample(Standard),
             chord_type,
+            chord_name: data_parameters.chords.name(chord_type).to_owned(),
+            chord,
+            tuning: data_parameters.tuning.clone(),
+            voicing,
+            inversion,
             frequencies,
             oscillators,
             effects: data_parameters
@@ -372,15 +1112,117 @@ impl DataPointParameters {
                 .iter()
                 .flat_map(|effect_distribution| effect_distribution.sample(&mut rng))
                 .collect(),
+            vibrato: data_parameters
+                .vibrato
+                .as_ref()
+                .and_then(|vibrato_distribution| vibrato_distribution.sample(&mut rng)),
+            tremolo: data_parameters
+                .tremolo
+                .as_ref()
+                .and_then(|tremolo_distribution| tremolo_distribution.sample(&mut rng)),
             wave_forms: data_parameters.wave_forms.clone(),
             num_samples: data_parameters.num_samples,
         }
     }
 
+    /// Like [`DataPointParameters::new`], but playing the single note with the given MIDI note number, detuned by
+    /// `detune` cents, at the given concert pitch instead of a sampled chord, as when rendering a MIDI file.
+    ///
+    /// Fails if the single note chord is not registered.
+    pub(crate) fn with_note(
+        data_parameters: &DataParameters,
+        seed: u64,
+        note_number: u32,
+        detune: f32,
+        a4_frequency: f32,
+    ) -> Result<Self, ConfigError> {
+        let chord = ChordType::from_valid_intervals(Vec::new());
+        let chord_type = data_parameters
+            .chords
+            .index_of(&chord)
+            .ok_or_else(|| ConfigError::UnregisteredChord(Vec::new()))?;
+        let parameters = Self::new(data_parameters, seed);
+        let base_frequency = parameters
+            .tuning
+            .note_number_to_frequency(note_number as f32 + detune / 100., a4_frequency);
+        Ok(Self {
+            base_frequency,
+            a4_frequency,
+            note: Some((note_number, detune)),
+            chord_type,
+            chord_name: data_parameters.chords.name(chord_type).to_owned(),
+            chord,
+            voicing: None,
+            inversion: None,
+            frequencies: vec![base_frequency],
+            ..parameters
+        })
+    }
+
     pub fn has_frequency(&self) -> bool {
         self.oscillators.iter().any(|osc| osc.has_frequency())
     }
 
+    /// Nearest MIDI note number of a frequency in the tuning and concert pitch of this data point.
+    pub fn midi_note(&self, frequency: f32) -> u32 {
+        self.tuning
+            .frequency_to_note_number(frequency, self.a4_frequency)
+            .round()
+            .clamp(0., 127.) as u32
+    }
+
+    /// Sorted MIDI note numbers of all frequencies without duplicates.
+    pub fn midi_notes(&self) -> Vec<u32> {
+        let mut notes: Vec<u32> = self
+            .frequencies
+            .iter()
+            .map(|&frequency| self.midi_note(frequency))
+            .collect();
+        notes.sort_unstable();
+        notes.dedup();
+        notes
+    }
+
+    /// Frequency multipliers for each sample caused by vibrato.
+    pub fn vibrato_factors(&self) -> Vec<f32> {
+        match &self.vibrato {
+            Some(vibrato) => vibrato.vibrato_factors(self.sample_rate, self.num_samples as usize),
+            None => vec![1.; self.num_samples as usize],
+        }
+    }
+
+    /// Realized frequency of the note at `note_index` in `frequencies` for each sample, including drift and vibrato.
+    ///
+    /// When oscillators or unison voices drift independently or are detuned, this is the amplitude weighted mean of
+    /// their frequencies.
+    pub fn frequency_trajectory(&self, note_index: usize) -> Vec<f32> {
+        let frequency = self.frequencies[note_index];
+        let num_samples = self.num_samples as usize;
+        let mut trajectory = vec![0.; num_samples];
+        let mut total_amplitude = 0.;
+        for (oscillator_index, oscillator) in self.oscillators.iter().enumerate() {
+            if oscillator.has_frequency() {
+                let seed = self
+                    .drift
+                    .seed(self.frequency_walk_seed, note_index, oscillator_index);
+                for (detune, seed, amplitude) in oscillator.voice_drifts(seed) {
+                    let detune_factor = (detune / 1200.).exp2();
+                    let drift_factors =
+                        self.drift
+                            .pitch_factors(seed, self.sample_rate, num_samples);
+                    for (value, drift_factor) in trajectory.iter_mut().zip(drift_factors) {
+                        *value += drift_factor * detune_factor * amplitude;
+                    }
+                    total_amplitude += amplitude;
+                }
+            }
+        }
+        for (value, vibrato_factor) in trajectory.iter_mut().zip(self.vibrato_factors()) {
+            *value *= frequency * vibrato_factor / total_amplitude;
+        }
+        trajectory
+    }
+
     pub fn generate(self) -> Result<DataPoint, AudioGenerationError> {
         DataPoint::new(self)
     }
@@ -402,4 +1244,164 @@ mod test {
             assert!((note_number_from_map - note_number).abs() < 0.01, "Note number: {note_number}  Frequency: {frequency}  Map: {map}  Note number from map: {note_number_from_map}");
         }
     }
+
+    #[test]
+    fn config_references_wave_forms() {
+        use super::{DataConfig, DataParameters, OctaveParameters, WaveForms};
+        use crate::{
+            config::{ConfigError, WaveFormSource},
+            parameters::oscillators::OscillatorTypeDistribution,
+            UniformI,
+        };
+
+        let wave_forms = WaveForms::new()
+            .load_dir_and_add("assets/custom_oscillators")
+            .and_then(|wave_forms| wave_forms.with_wavetable([0, 1]))
+            .unwrap();
+        let num_wave_forms = wave_forms.num_wave_forms();
+        let parameters = DataParameters::new(
+            44100,
+            (50., 2000.),
+            (0.5, 3.),
+            [0],
+            OctaveParameters::new(0.5, 0.3, 90., 10_000.).unwrap(),
+            wave_forms,
+            256,
+        )
+        .and_then(|parameters| {
+            parameters.with_oscillator(
+                OscillatorTypeDistribution::Custom(UniformI::new(0, num_wave_forms)),
+                1.,
+                (0.1, 0.2),
+            )
+        })
+        .unwrap();
+
+        let text = toml::to_string(&parameters.config()).unwrap();
+        assert!(
+            text.len() < 4096,
+            "Config embeds wave forms: {} bytes",
+            text.len()
+        );
+        let config: DataConfig = toml::from_str(&text).unwrap();
+        let resolved = config.clone().resolve("").unwrap();
+        assert_eq!(resolved.num_wave_forms(), num_wave_forms);
+        assert_eq!(
+            resolved.wave_forms.sources(),
+            [
+                WaveFormSource::Directory("assets/custom_oscillators".into()),
+                WaveFormSource::Wavetable(vec![0, 1])
+            ]
+        );
+
+        let text = text.replace("assets/custom_oscillators", "assets/missing");
+        let config: DataConfig = toml::from_str(&text).unwrap();
+        assert!(matches!(config.resolve(""), Err(ConfigError::Io { .. })));
+
+        let parameters = parameters
+            .with_oscillator(
+                OscillatorTypeDistribution::Custom(UniformI::new(0, num_wave_forms + 1)),
+                1.,
+                (0.1, 0.2),
+            )
+            .unwrap();
+        assert!(matches!(
+            parameters.config().resolve(""),
+            Err(ConfigError::MissingBank { .. })
+        ));
+    }
+
+    #[test]
+    fn config_paths_are_relative_to_the_config_file() {
+        use super::DataParameters;
+
+        let parameters = DataParameters::load_config("configs/custom_oscillators.toml").unwrap();
+        assert_eq!(parameters.num_wave_forms(), 12);
+    }
+
+    #[test]
+    fn parameters_are_deserialized_through_their_config() {
+        use super::DataParameters;
+
+        let parameters = DataParameters::load_config("configs/synth.toml").unwrap();
+        let mut value = serde_json::to_value(&parameters).unwrap();
+        let deserialized: DataParameters = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&deserialized).unwrap(), value);
+
+        value["possible_chords"] = serde_json::json!([]);
+        assert!(serde_json::from_value::<DataParameters>(value).is_err());
+    }
+
+    #[test]
+    fn progression_roots_stay_in_the_midi_range() {
+        assert_eq!(super::clamp_to_midi_range(-3), 9);
+        assert_eq!(super::clamp_to_midi_range(60), 60);
+        assert_eq!(super::clamp_to_midi_range(130), 118);
+        assert_eq!(super::clamp_to_midi_range(127), 127);
+    }
+
+    #[test]
+    fn voiced_notes_stay_in_the_frequency_range() {
+        use super::OctaveParameters;
+
+        let octave_parameters = OctaveParameters::new(0., 0., 100., 400.).unwrap();
+        for (frequency, expected) in [(30., 120.), (100., 100.), (250., 250.), (1000., 250.)] {
+            let folded = octave_parameters.fold_into_range(frequency);
+            assert!((folded - expected).abs() < 1e-3, "{frequency} -> {folded}");
+        }
+    }
+
+    #[test]
+    fn folded_voicings_keep_the_root_and_report_the_lowest_note() {
+        use super::OctaveParameters;
+        use crate::{parameters::voicing, tuning::Tuning};
+
+        // A drop 2 seventh chord on 110 Hz puts the fifth at 82.5 Hz, below the range, so it is folded up and the
+        // root becomes the lowest note.
+        let octave_parameters = OctaveParameters::new(0., 0., 100., 400.).unwrap();
+        let positions = [0, 4, -5, 10];
+        let frequencies = super::voiced_frequencies(
+            &positions,
+            110.,
+            &Tuning::JustIntonation,
+            &octave_parameters,
+        );
+        assert_eq!(frequencies[0], 110.);
+        assert!((frequencies[2] - 165.).abs() < 1e-3, "{frequencies:?}");
+        assert!(frequencies
+            .iter()
+            .all(|frequency| (100. ..=400.).contains(frequency)));
+        assert_eq!(voicing::realized_inversion(&frequencies, 4), 0);
+    }
+
+    #[test]
+    fn every_violation_is_reported() {
+        use super::OctaveParameters;
+        use crate::config::ConfigError;
+
+        match OctaveParameters::new(1.5, -0.1, 0., 100.) {
+            Err(ConfigError::Multiple(errors)) => assert_eq!(errors.len(), 3, "{errors:?}"),
+            result => panic!("Expected several violations: {result:?}"),
+        }
+
+        // Dampening above 1 and a rate of 0 would only fail while generating.
+        let text = std::fs::read_to_string("configs/synth.toml")
+            .unwrap()
+            .replace(
+                "dampening_distribution = { min = 0.9, max = 0.9 }",
+                "dampening_distribution = { min = 0.9, max = 1.5 }",
+            )
+            + "
+[vibrato]
+rate_distribution = { min = 0.0, max = 5.0 }
+depth_distribution = { min = 10.0, max = 20.0 }
+delay_distribution = { min = 0.0, max = 0.1 }
+probability = 2.0
+";
+        let config: super::DataConfig = toml::from_str(&text).unwrap();
+        match config.resolve("") {
+            Err(ConfigError::Multiple(errors)) => assert_eq!(errors.len(), 3, "{errors:?}"),
+            result => panic!("Expected several violations: {:?}", result.err()),
+        }
+    }
 }
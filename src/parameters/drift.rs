@@ -0,0 +1,331 @@
+use std::f32::consts::TAU;
+
+use flexblock_synth::modules::{Module, RandomWalk};
+use rand::{prelude::Distribution, Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    hash,
+    log_uniform::LogUniform,
+    UniformF,
+};
+
+/// Distribution over the models used for slow pitch drift of notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DriftModelDistribution {
+    /// Dampened random walk. The standard deviation is in cents.
+    RandomWalk {
+        std_dev_distribution: LogUniform,
+        dampening_distribution: UniformF,
+    },
+    /// Ornstein-Uhlenbeck process in cents which reverts to the note frequency.
+    /// The standard deviation is that of the stationary distribution and the time constant is in seconds.
+    OrnsteinUhlenbeck {
+        std_dev_distribution: LogUniform,
+        time_constant_distribution: LogUniform,
+    },
+    /// Linear glide from the note frequency over the whole clip. The distribution is over the total change in cents.
+    Glide(UniformF),
+}
+
+impl DriftModelDistribution {
+    pub fn random_walk(std_dev_range: (f32, f32), dampening_range: (f32, f32)) -> Self {
+        assert!(
+            std_dev_range.0 >= 0.0,
+            "Standard deviation range must be non-negative."
+        );
+        assert!(
+            std_dev_range.1 >= std_dev_range.0,
+            "Standard deviation range maximum must be no less than the minimum."
+        );
+        assert!(
+            dampening_range.0 >= 0.0 && dampening_range.1 <= 1.0,
+            "Dampening range must be within [0;1]."
+        );
+        assert!(
+            dampening_range.1 >= dampening_range.0,
+            "Dampening range maximum must be no less than the minimum."
+        );
+        Self::RandomWalk {
+            std_dev_distribution: LogUniform::from_valid_tuple(std_dev_range),
+            dampening_distribution: UniformF::new(dampening_range.0, dampening_range.1),
+        }
+    }
+
+    pub fn ornstein_uhlenbeck(std_dev_range: (f32, f32), time_constant_range: (f32, f32)) -> Self {
+        assert!(
+            std_dev_range.0 >= 0.0,
+            "Standard deviation range must be non-negative."
+        );
+        assert!(
+            std_dev_range.1 >= std_dev_range.0,
+            "Standard deviation range maximum must be no less than the minimum."
+        );
+        assert!(
+            time_constant_range.0 > 0.0,
+            "Time constant range must be positive."
+        );
+        Self::OrnsteinUhlenbeck {
+            std_dev_distribution: LogUniform::from_valid_tuple(std_dev_range),
+            time_constant_distribution: LogUniform::from_valid_tuple(time_constant_range),
+        }
+    }
+
+    pub fn glide(cent_range: (f32, f32)) -> Self {
+        assert!(
+            cent_range.1 >= cent_range.0,
+            "Glide range maximum must be no less than the minimum."
+        );
+        Self::Glide(UniformF::new(cent_range.0, cent_range.1))
+    }
+}
+
+impl DriftModelDistribution {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        match self {
+            DriftModelDistribution::RandomWalk {
+                std_dev_distribution,
+                dampening_distribution,
+            } => {
+                violations.add(std_dev_distribution.validate("drift standard deviation"));
+                violations.check_range(
+                    "drift dampening",
+                    dampening_distribution.range(),
+                    (0., 1.),
+                    "the range must be within [0;1]",
+                );
+            }
+            DriftModelDistribution::OrnsteinUhlenbeck {
+                std_dev_distribution,
+                time_constant_distribution,
+            } => {
+                violations.add(std_dev_distribution.validate("drift standard deviation"));
+                violations.add(time_constant_distribution.validate("drift time constant"));
+            }
+            DriftModelDistribution::Glide(cent_distribution) => violations.check_range(
+                "glide",
+                cent_distribution.range(),
+                (f32::MIN, f32::MAX),
+                "the range must be finite and non-empty",
+            ),
+        }
+        violations.into_result()
+    }
+}
+
+impl Distribution<DriftModel> for DriftModelDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DriftModel {
+        match self {
+            DriftModelDistribution::RandomWalk {
+                std_dev_distribution,
+                dampening_distribution,
+            } => DriftModel::RandomWalk {
+                std_dev: std_dev_distribution.sample(rng),
+                dampening: dampening_distribution.sample(rng),
+            },
+            DriftModelDistribution::OrnsteinUhlenbeck {
+                std_dev_distribution,
+                time_constant_distribution,
+            } => DriftModel::OrnsteinUhlenbeck {
+                std_dev: std_dev_distribution.sample(rng),
+                time_constant: time_constant_distribution.sample(rng),
+            },
+            DriftModelDistribution::Glide(cent_distribution) => {
+                DriftModel::Glide(cent_distribution.sample(rng))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DriftModel {
+    RandomWalk { std_dev: f32, dampening: f32 },
+    OrnsteinUhlenbeck { std_dev: f32, time_constant: f32 },
+    Glide(f32),
+}
+
+impl DriftModel {
+    /// Frequency multipliers for each sample of a drifting note.
+    pub fn pitch_factors(&self, seed: u64, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+        match *self {
+            DriftModel::RandomWalk { std_dev, dampening } => {
+                let walk_std_dev = (2f32).powf(std_dev / 1200.) - 1.;
+                let mut walk = RandomWalk::new(rng, walk_std_dev, dampening, sample_rate) + 1.;
+                (0..num_samples)
+                    .map(|sample_num| walk.next(sample_num as u64))
+                    .collect()
+            }
+            DriftModel::OrnsteinUhlenbeck {
+                std_dev,
+                time_constant,
+            } => {
+                let decay = (-1. / (time_constant * sample_rate as f32)).exp();
+                let step_std_dev = std_dev * (1. - decay * decay).sqrt();
+                let mut cents = 0.;
+                (0..num_samples)
+                    .map(|_| {
+                        let factor = (cents / 1200f32).exp2();
+                        cents = cents * decay + step_std_dev * standard_normal(&mut rng);
+                        factor
+                    })
+                    .collect()
+            }
+            DriftModel::Glide(cents) => (0..num_samples)
+                .map(|sample_num| (cents * sample_num as f32 / num_samples as f32 / 1200.).exp2())
+                .collect(),
+        }
+    }
+}
+
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f32 {
+    // Box-Muller transform.
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.);
+    let u2: f32 = rng.gen_range(0f32..1.);
+    (-2. * u1.ln()).sqrt() * (TAU * u2).cos()
+}
+
+/// Distribution over pitch drift, including whether notes and oscillators drift together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftDistribution {
+    model_distribution: DriftModelDistribution,
+    shared_between_notes: bool,
+    shared_between_oscillators: bool,
+}
+
+impl DriftDistribution {
+    /// Drift that is independent for every note and oscillator.
+    pub fn new(model_distribution: DriftModelDistribution) -> Self {
+        Self {
+            model_distribution,
+            shared_between_notes: false,
+            shared_between_oscillators: false,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        self.model_distribution.validate()
+    }
+
+    /// If set, all notes of a data point follow the same drift.
+    pub fn shared_between_notes(mut self, shared: bool) -> Self {
+        self.shared_between_notes = shared;
+        self
+    }
+
+    /// If set, all oscillators playing a note follow the same drift.
+    pub fn shared_between_oscillators(mut self, shared: bool) -> Self {
+        self.shared_between_oscillators = shared;
+        self
+    }
+}
+
+impl Distribution<DriftParameters> for DriftDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> DriftParameters {
+        DriftParameters {
+            model: self.model_distribution.sample(rng),
+            shared_between_notes: self.shared_between_notes,
+            shared_between_oscillators: self.shared_between_oscillators,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftParameters {
+    model: DriftModel,
+    shared_between_notes: bool,
+    shared_between_oscillators: bool,
+}
+
+impl DriftParameters {
+    pub fn model(&self) -> DriftModel {
+        self.model
+    }
+
+    /// Seed for the drift of the given note and oscillator. Shared drifts get the same seed.
+    pub fn seed(&self, seed: u64, note_index: usize, oscillator_index: usize) -> u64 {
+        let note_index = if self.shared_between_notes {
+            0
+        } else {
+            note_index as u64 + 1
+        };
+        let oscillator_index = if self.shared_between_oscillators {
+            0
+        } else {
+            oscillator_index as u64 + 1
+        };
+        hash(
+            seed.wrapping_add(hash(note_index))
+                .wrapping_add(oscillator_index),
+        )
+    }
+
+    pub fn pitch_factors(&self, seed: u64, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        self.model.pitch_factors(seed, sample_rate, num_samples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DriftModel;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn cents(factors: &[f32]) -> Vec<f32> {
+        factors.iter().map(|factor| factor.log2() * 1200.).collect()
+    }
+
+    #[test]
+    fn ornstein_uhlenbeck_reverts_to_the_note() {
+        let model = DriftModel::OrnsteinUhlenbeck {
+            std_dev: 20.,
+            time_constant: 0.01,
+        };
+        let factors = model.pitch_factors(1, SAMPLE_RATE, 100_000);
+        assert_eq!(factors.len(), 100_000);
+        assert_eq!(factors[0], 1.);
+
+        let cents = cents(&factors);
+        let mean = cents.iter().sum::<f32>() / cents.len() as f32;
+        let std_dev = (cents.iter().map(|cent| (cent - mean).powi(2)).sum::<f32>()
+            / cents.len() as f32)
+            .sqrt();
+        assert!(mean.abs() < 2., "Mean: {mean}");
+        assert!((std_dev - 20.).abs() < 2., "Standard deviation: {std_dev}");
+    }
+
+    #[test]
+    fn glide_moves_linearly_over_the_clip() {
+        let factors = DriftModel::Glide(-200.).pitch_factors(1, SAMPLE_RATE, 1000);
+        let cents = cents(&factors);
+        assert_eq!(cents[0], 0.);
+        assert!((cents[500] + 100.).abs() < 1e-2, "{}", cents[500]);
+        assert!((cents[999] + 199.8).abs() < 1e-2, "{}", cents[999]);
+    }
+
+    #[test]
+    fn random_walk_is_reproducible() {
+        let model = DriftModel::RandomWalk {
+            std_dev: 10.,
+            dampening: 0.5,
+        };
+        let factors = model.pitch_factors(1, SAMPLE_RATE, 1000);
+        assert_eq!(factors.len(), 1000);
+        assert!(factors
+            .iter()
+            .all(|factor| factor.is_finite() && *factor > 0.));
+        assert_eq!(factors, model.pitch_factors(1, SAMPLE_RATE, 1000));
+
+        let still = DriftModel::RandomWalk {
+            std_dev: 0.,
+            dampening: 0.5,
+        };
+        assert!(still
+            .pitch_factors(1, SAMPLE_RATE, 1000)
+            .iter()
+            .all(|&factor| factor == 1.));
+    }
+}
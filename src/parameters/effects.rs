@@ -2,7 +2,10 @@ use float_ord::FloatOrd;
 use rand::{prelude::Distribution, Rng};
 use serde::{Deserialize, Serialize};
 
-use crate::log_uniform::LogUniform;
+use crate::{
+    config::{ConfigError, Violations},
+    log_uniform::LogUniform,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EffectTypeDistribution {
@@ -11,8 +14,9 @@ pub enum EffectTypeDistribution {
 }
 
 impl EffectTypeDistribution {
+    /// The power range is checked when the effect distribution is created.
     pub fn distortion(power_range: (f32, f32)) -> Self {
-        Self::Distortion(LogUniform::from_tuple(power_range))
+        Self::Distortion(LogUniform::from_valid_tuple(power_range))
     }
 
     pub fn normalize() -> Self {
@@ -38,16 +42,27 @@ pub struct EffectDistribution {
 }
 
 impl EffectDistribution {
-    pub fn new(effect_type_distribution: EffectTypeDistribution, probability: f64) -> Self {
-        assert!(probability > 0.0, "Probability must be positive.");
-        assert!(
-            probability <= 1.0,
-            "Probability must be less than or equal to 1."
-        );
-        Self {
+    pub fn new(
+        effect_type_distribution: EffectTypeDistribution,
+        probability: f64,
+    ) -> Result<Self, ConfigError> {
+        let effect = Self {
             effect_type_distribution,
             probability,
+        };
+        effect.validate()?;
+        Ok(effect)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check_probability("effect probability", self.probability, false);
+        if let EffectTypeDistribution::Distortion(power_distribution) =
+            &self.effect_type_distribution
+        {
+            violations.add(power_distribution.validate("distortion power"));
         }
+        violations.into_result()
     }
 
     pub fn probability(&self) -> f64 {
@@ -1,4 +1,11 @@
 mod data;
+pub mod drift;
 pub mod effects;
+pub mod modulation;
+pub mod noise;
+pub mod notes;
 pub mod oscillators;
+pub mod sampler;
+pub mod voicing;
+pub mod wavetable;
 pub use data::*;
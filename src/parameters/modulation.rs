@@ -0,0 +1,195 @@
+use std::f32::consts::TAU;
+
+use rand::{prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    log_uniform::LogUniform,
+    UniformF,
+};
+
+/// Distribution over low frequency oscillators used for vibrato and tremolo.
+///
+/// The unit of the depth depends on what is modulated: cents for vibrato and decibels for tremolo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LfoDistribution {
+    rate_distribution: LogUniform,
+    depth_distribution: LogUniform,
+    delay_distribution: UniformF,
+    probability: f64,
+}
+
+impl LfoDistribution {
+    /// `rate_range` is in Hz and `delay_range` in seconds.
+    pub fn new(
+        rate_range: (f32, f32),
+        depth_range: (f32, f32),
+        delay_range: (f32, f32),
+        probability: f64,
+    ) -> Result<Self, ConfigError> {
+        let lfo_distribution =
+            Self::from_unchecked_ranges(rate_range, depth_range, delay_range, probability);
+        lfo_distribution.validate("LFO")?;
+        Ok(lfo_distribution)
+    }
+
+    /// Must be followed by [`Self::validate`], which names the use of the oscillator in its errors.
+    pub(crate) fn from_unchecked_ranges(
+        rate_range: (f32, f32),
+        depth_range: (f32, f32),
+        delay_range: (f32, f32),
+        probability: f64,
+    ) -> Self {
+        Self {
+            rate_distribution: LogUniform::from_valid_tuple(rate_range),
+            depth_distribution: LogUniform::from_valid_tuple(depth_range),
+            delay_distribution: UniformF::new(delay_range.0, delay_range.1),
+            probability,
+        }
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// Checks the ranges of a deserialized distribution. `name` is the use of the oscillator, such as vibrato.
+    pub(crate) fn validate(&self, name: &'static str) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.add(self.rate_distribution.validate(name));
+        violations.add(self.depth_distribution.validate(name));
+        violations.check_range(
+            name,
+            self.delay_distribution.range(),
+            (0., f32::MAX),
+            "the delay range must be finite, non-negative and non-empty",
+        );
+        violations.check_probability(name, self.probability, false);
+        violations.into_result()
+    }
+}
+
+impl Distribution<Option<LfoParameters>> for LfoDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<LfoParameters> {
+        rng.gen_bool(self.probability).then(|| LfoParameters {
+            rate: self.rate_distribution.sample(rng),
+            depth: self.depth_distribution.sample(rng),
+            delay: self.delay_distribution.sample(rng),
+            phase: rng.gen_range(0f32..1.),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LfoParameters {
+    rate: f32,
+    depth: f32,
+    delay: f32,
+    phase: f32,
+}
+
+impl LfoParameters {
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    pub fn delay(&self) -> f32 {
+        self.delay
+    }
+
+    /// Value of the oscillator at the given time in seconds, scaled by the depth.
+    ///
+    /// The oscillator is silent until the delay has passed, after which it fades in over one period.
+    pub fn value(&self, time: f32) -> f32 {
+        self.sine(time) * self.depth * self.fade_in(time)
+    }
+
+    /// Factor in `[0;1]` by which the depth is scaled, rising linearly over one period after the delay.
+    fn fade_in(&self, time: f32) -> f32 {
+        ((time - self.delay) * self.rate).clamp(0., 1.)
+    }
+
+    fn sine(&self, time: f32) -> f32 {
+        (TAU * (self.rate * (time - self.delay) + self.phase)).sin()
+    }
+
+    /// Frequency multipliers for each sample when the oscillator is used as vibrato with a depth in cents.
+    pub fn vibrato_factors(&self, sample_rate: u32, num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|sample_num| (self.value(sample_num as f32 / sample_rate as f32) / 1200.).exp2())
+            .collect()
+    }
+
+    /// Multiplies the buffer with the oscillator used as tremolo with a depth in decibels.
+    ///
+    /// The gain swings between unity and `-depth` dB so tremolo never increases the amplitude.
+    pub fn apply_tremolo(&self, sample_rate: u32, buffer: &mut [f32]) {
+        for (sample_num, sample) in buffer.iter_mut().enumerate() {
+            let time = sample_num as f32 / sample_rate as f32;
+            let attenuation = self.depth * self.fade_in(time) * (1. - self.sine(time)) * 0.5;
+            *sample *= 10f32.powf(-attenuation / 20.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LfoParameters;
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn lfo(depth: f32) -> LfoParameters {
+        LfoParameters {
+            rate: 5.,
+            depth,
+            delay: 0.5,
+            phase: 0.,
+        }
+    }
+
+    /// Number of times the values cross `level` from below.
+    fn rising_crossings(values: &[f32], level: f32) -> usize {
+        values
+            .windows(2)
+            .filter(|pair| pair[0] < level && pair[1] >= level)
+            .count()
+    }
+
+    #[test]
+    fn vibrato_follows_rate_depth_and_delay() {
+        let factors = lfo(100.).vibrato_factors(SAMPLE_RATE, 2 * SAMPLE_RATE as usize);
+        let (delayed, modulated) = factors.split_at(SAMPLE_RATE as usize / 2);
+        assert!(delayed.iter().all(|&factor| factor == 1.));
+
+        // After the delay and one period of fade-in, a full second is left at 5 Hz.
+        let steady = &modulated[SAMPLE_RATE as usize / 5..][..SAMPLE_RATE as usize];
+        assert_eq!(rising_crossings(steady, (50f32 / 1200.).exp2()), 5);
+        let max = steady.iter().copied().fold(0., f32::max);
+        assert!((max - (100f32 / 1200.).exp2()).abs() < 1e-4, "{max}");
+    }
+
+    #[test]
+    fn tremolo_follows_rate_depth_and_delay() {
+        let depth = 6.;
+        let mut buffer = vec![1.; 2 * SAMPLE_RATE as usize];
+        lfo(depth).apply_tremolo(SAMPLE_RATE, &mut buffer);
+        let (delayed, modulated) = buffer.split_at(SAMPLE_RATE as usize / 2);
+        assert!(delayed.iter().all(|&gain| gain == 1.));
+
+        let steady = &modulated[SAMPLE_RATE as usize / 5..][..SAMPLE_RATE as usize];
+        let min = steady.iter().copied().fold(1., f32::min);
+        assert!((min - 10f32.powf(-depth / 20.)).abs() < 1e-4, "{min}");
+        assert!(steady.iter().all(|&gain| gain <= 1.));
+        assert_eq!(rising_crossings(steady, 10f32.powf(-depth / 40.)), 5);
+
+        // Three quarters into the fade-in the sine is at its minimum, so the attenuation is three quarters of
+        // the depth.
+        let gain = modulated[SAMPLE_RATE as usize * 3 / 20];
+        let attenuation = -20. * gain.log10();
+        assert!((attenuation - depth * 0.75).abs() < 1e-3, "{attenuation}");
+    }
+}
@@ -0,0 +1,129 @@
+use std::f32::consts::{LN_2, TAU};
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+use crate::Audio;
+
+/// Spectral color of a noise generator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoiseColor {
+    /// Flat power spectrum.
+    White,
+    /// Power falls by 3 dB per octave.
+    Pink,
+    /// Power falls by 6 dB per octave. Also called red noise.
+    Brown,
+    /// Power rises by 3 dB per octave.
+    Blue,
+    /// Power rises by 6 dB per octave.
+    Violet,
+}
+
+impl NoiseColor {
+    /// Exponent of the frequency in the power spectral density.
+    fn exponent(self) -> f32 {
+        match self {
+            NoiseColor::White => 0.,
+            NoiseColor::Pink => -1.,
+            NoiseColor::Brown => -2.,
+            NoiseColor::Blue => 1.,
+            NoiseColor::Violet => 2.,
+        }
+    }
+}
+
+fn white_noise(seed: u64, num_samples: usize) -> Vec<f32> {
+    let mut rng = Pcg64Mcg::seed_from_u64(seed);
+    (0..num_samples).map(|_| rng.gen_range(-1f32..1.)).collect()
+}
+
+fn normalize(samples: &mut [f32]) {
+    let peak = samples.iter().map(|x| x.abs()).fold(0., f32::max);
+    if peak > 0. {
+        samples.iter_mut().for_each(|x| *x /= peak);
+    }
+}
+
+/// Generates noise of the given color by shaping the spectrum of white noise. The result is normalized to a peak of 1.
+pub fn colored_noise(color: NoiseColor, seed: u64, num_samples: usize) -> Vec<f32> {
+    let white = white_noise(seed, num_samples);
+    if color == NoiseColor::White {
+        return white;
+    }
+    let mut spectrum = Audio::from_samples(white, 1).fft();
+    let exponent = color.exponent();
+    for (index, value) in spectrum.iter_mut().enumerate() {
+        let frequency = index.min(num_samples - index);
+        *value *= if frequency == 0 {
+            0.
+        } else {
+            (frequency as f32).powf(exponent * 0.5)
+        };
+    }
+    let mut samples = Audio::from_spectrum(spectrum, 1).samples;
+    normalize(&mut samples);
+    samples
+}
+
+/// White noise passed through a band-pass filter centered on the frequency trajectory.
+///
+/// `bandwidth` is the width of the pass band in cents. The result is normalized to a peak of 1.
+pub fn noise_band(frequencies: &[f32], bandwidth: f32, seed: u64, sample_rate: u32) -> Vec<f32> {
+    let white = white_noise(seed, frequencies.len());
+    let octaves = bandwidth / 1200.;
+    let nyquist = sample_rate as f32 * 0.5;
+
+    // Band-pass biquad with constant peak gain, recomputed every sample to follow the frequency.
+    let (mut x1, mut x2, mut y1, mut y2) = (0., 0., 0., 0.);
+    let mut samples: Vec<f32> = white
+        .into_iter()
+        .zip(frequencies)
+        .map(|(x, &frequency)| {
+            let omega = TAU * frequency.min(nyquist * 0.99) / sample_rate as f32;
+            let alpha = omega.sin() * (LN_2 * 0.5 * octaves * omega / omega.sin()).sinh();
+            let a0 = 1. + alpha;
+            let y = (alpha * x - alpha * x2 + 2. * omega.cos() * y1 - (1. - alpha) * y2) / a0;
+            (x2, x1, y2, y1) = (x1, x, y1, y);
+            y
+        })
+        .collect();
+    normalize(&mut samples);
+    samples
+}
+
+#[cfg(test)]
+mod test {
+    use super::NoiseColor;
+    use crate::Audio;
+
+    #[test]
+    fn colors_have_their_spectral_slope() {
+        let num_samples = 1 << 16;
+        for color in [
+            NoiseColor::White,
+            NoiseColor::Pink,
+            NoiseColor::Brown,
+            NoiseColor::Blue,
+            NoiseColor::Violet,
+        ] {
+            let spectrum =
+                Audio::from_samples(super::colored_noise(color, 1, num_samples), 1).fft();
+            let mean_power = |bins: std::ops::Range<usize>| {
+                let len = bins.len() as f32;
+                spectrum[bins]
+                    .iter()
+                    .map(|value| value.norm_sqr())
+                    .sum::<f32>()
+                    / len
+            };
+            // Four octaves apart.
+            let slope = (mean_power(1600..3200) / mean_power(100..200)).log2() / 4.;
+            assert!(
+                (slope - color.exponent()).abs() < 0.2,
+                "Color: {color:?}  Slope: {slope}"
+            );
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use rand::{prelude::Distribution, seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    UniformF,
+};
+
+/// Distribution over MIDI note numbers on the semitone grid, detuned by a separately sampled number of cents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteDistribution {
+    /// MIDI note numbers and their weights.
+    notes: Vec<(u32, f64)>,
+    detune_distribution: UniformF,
+}
+
+impl NoteDistribution {
+    /// Every note in the inclusive range `note_range` is equally likely.
+    pub fn range(note_range: (u32, u32), detune_range: (f32, f32)) -> Self {
+        assert!(
+            note_range.0 <= note_range.1,
+            "Invalid note range. Range: {note_range:?}"
+        );
+        let notes: Vec<(u32, f64)> = (note_range.0..=note_range.1)
+            .map(|note| (note, 1.))
+            .collect();
+        Self::weighted(notes, detune_range)
+    }
+
+    /// Notes are chosen with probability proportional to their weight.
+    pub fn weighted<A>(notes: A, detune_range: (f32, f32)) -> Self
+    where
+        A: Into<Vec<(u32, f64)>>,
+    {
+        let notes = notes.into();
+        assert!(!notes.is_empty(), "No notes provided.");
+        assert!(
+            notes
+                .iter()
+                .all(|&(note, weight)| note < 128 && weight >= 0.),
+            "Notes must be MIDI note numbers with non-negative weights. Notes: {notes:?}"
+        );
+        assert!(
+            notes.iter().any(|&(_, weight)| weight > 0.),
+            "At least one note must have a positive weight."
+        );
+        assert!(
+            detune_range.0 <= detune_range.1,
+            "Invalid detune range. Range: {detune_range:?}"
+        );
+        Self {
+            notes,
+            detune_distribution: UniformF::new(detune_range.0, detune_range.1),
+        }
+    }
+}
+
+impl NoteDistribution {
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(!self.notes.is_empty(), || ConfigError::Empty("notes"));
+        for &(note, weight) in self.notes.iter() {
+            violations.check(note < 128, || ConfigError::OutOfRange {
+                name: "MIDI note number",
+                value: note as f64,
+                interval: "[0;127]",
+            });
+            violations.check(weight.is_finite() && weight >= 0., || {
+                ConfigError::OutOfRange {
+                    name: "note weight",
+                    value: weight,
+                    interval: "[0;inf)",
+                }
+            });
+        }
+        violations.check(
+            self.notes.is_empty() || self.notes.iter().any(|&(_, weight)| weight > 0.),
+            || ConfigError::Empty("notes with a positive weight"),
+        );
+        violations.check_range(
+            "note detune",
+            self.detune_distribution.range(),
+            (f32::MIN, f32::MAX),
+            "the range must be finite and non-empty",
+        );
+        violations.into_result()
+    }
+}
+
+impl Distribution<(u32, f32)> for NoteDistribution {
+    /// Samples a MIDI note number and a detune in cents.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> (u32, f32) {
+        let &(note, _) = self
+            .notes
+            .choose_weighted(rng, |&(_, weight)| weight)
+            .unwrap();
+        (note, self.detune_distribution.sample(rng))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::prelude::Distribution;
+    use rand_pcg::Pcg64Mcg;
+
+    use super::NoteDistribution;
+    use crate::parameters::{DataParameters, DataPointParameters};
+
+    #[test]
+    fn notes_follow_their_weights_and_detune_range() {
+        let distribution = NoteDistribution::weighted([(60, 1.), (62, 0.), (64, 3.)], (-20., 20.));
+        let mut rng = Pcg64Mcg::new(1);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            let (note, detune) = distribution.sample(&mut rng);
+            assert!((-20. ..20.).contains(&detune), "Detune: {detune}");
+            counts[(note as usize - 60) / 2] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        let ratio = counts[2] as f32 / counts[0] as f32;
+        assert!((ratio - 3.).abs() < 0.3, "Counts: {counts:?}");
+    }
+
+    #[test]
+    fn base_frequency_is_the_detuned_note() {
+        let data_parameters = DataParameters::load_config("configs/synth.toml")
+            .unwrap()
+            .with_note_distribution(NoteDistribution::range((60, 60), (10., 10.)));
+        let parameters = DataPointParameters::new(&data_parameters, 0);
+        assert_eq!(parameters.note, Some((60, 10.)));
+        let expected = crate::note_number_to_frequency(60.) * (10f32 / 1200.).exp2();
+        assert!(
+            (parameters.base_frequency - expected).abs() < 1e-3,
+            "Base frequency: {}",
+            parameters.base_frequency
+        );
+    }
+}
@@ -1,14 +1,24 @@
+use std::sync::Arc;
+
 use flexblock_synth::modules::{
-    CustomOscillator, Module, NoiseOscillator, PulseOscillator, RandomWalk, SawOscillator,
-    SineOscillator, TriangleOscillator,
+    CustomOscillator, Module, NoiseOscillator, PulseOscillator, SawOscillator, SineOscillator,
+    TriangleOscillator,
 };
 use rand::{prelude::Distribution, Rng, SeedableRng};
 use rand_pcg::Pcg64Mcg;
 use serde::{Deserialize, Serialize};
 
-use crate::{log_uniform::LogUniform, UniformF, UniformI};
-
-use super::WaveForms;
+use super::{
+    drift::DriftParameters,
+    noise::{self, NoiseColor},
+    wavetable::MorphDistribution,
+    WaveForms,
+};
+use crate::{
+    config::{ConfigError, Violations},
+    log_uniform::LogUniform,
+    UniformCount, UniformF, UniformI,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OscillatorTypeDistribution {
@@ -17,7 +27,19 @@ pub enum OscillatorTypeDistribution {
     Pulse(UniformF),
     Triangle,
     Custom(UniformI),
+    /// Morphs between the wave forms of a wavetable. The integer distribution is over wavetable indices.
+    Wavetable(UniformI, MorphDistribution),
+    /// Plays recordings of a sampled instrument. The integer distribution is over instrument indices.
+    Sampler(UniformI),
+    /// White noise.
     Noise,
+    ColoredNoise(NoiseColor),
+    /// Noise band-passed around the note frequency with a bandwidth in cents.
+    /// The oscillator counts as having a frequency if the bandwidth is at most `max_pitched_bandwidth`.
+    NoiseBand {
+        bandwidth_distribution: LogUniform,
+        max_pitched_bandwidth: f32,
+    },
 }
 
 impl OscillatorTypeDistribution {
@@ -28,7 +50,22 @@ impl OscillatorTypeDistribution {
             OscillatorTypeDistribution::Pulse(_) => true,
             OscillatorTypeDistribution::Triangle => true,
             OscillatorTypeDistribution::Custom(_) => true,
+            OscillatorTypeDistribution::Wavetable(_, _) => true,
+            OscillatorTypeDistribution::Sampler(_) => true,
             OscillatorTypeDistribution::Noise => false,
+            OscillatorTypeDistribution::ColoredNoise(_) => false,
+            OscillatorTypeDistribution::NoiseBand {
+                bandwidth_distribution,
+                max_pitched_bandwidth,
+            } => bandwidth_distribution.min() <= *max_pitched_bandwidth,
+        }
+    }
+
+    /// The bandwidth range is checked when the oscillator distribution is created.
+    pub fn noise_band(bandwidth_range: (f32, f32), max_pitched_bandwidth: f32) -> Self {
+        Self::NoiseBand {
+            bandwidth_distribution: LogUniform::from_valid_tuple(bandwidth_range),
+            max_pitched_bandwidth,
         }
     }
 }
@@ -45,16 +82,152 @@ impl Distribution<OscillatorType> for OscillatorTypeDistribution {
             OscillatorTypeDistribution::Custom(wave_form_distr) => {
                 OscillatorType::Custom(wave_form_distr.sample(rng), rng.next_u64())
             }
-            OscillatorTypeDistribution::Noise => OscillatorType::Noise(rng.next_u64()),
+            OscillatorTypeDistribution::Wavetable(wavetable_distr, morph_distr) => {
+                OscillatorType::Wavetable {
+                    wavetable: wavetable_distr.sample(rng),
+                    morph: morph_distr.sample(rng),
+                    seed: rng.next_u64(),
+                }
+            }
+            OscillatorTypeDistribution::Sampler(instrument_distr) => {
+                OscillatorType::Sampler(instrument_distr.sample(rng))
+            }
+            OscillatorTypeDistribution::Noise => {
+                OscillatorType::Noise(NoiseColor::White, rng.next_u64())
+            }
+            OscillatorTypeDistribution::ColoredNoise(color) => {
+                OscillatorType::Noise(*color, rng.next_u64())
+            }
+            OscillatorTypeDistribution::NoiseBand {
+                bandwidth_distribution,
+                max_pitched_bandwidth,
+            } => {
+                let bandwidth = bandwidth_distribution.sample(rng);
+                OscillatorType::NoiseBand {
+                    bandwidth,
+                    pitched: bandwidth <= *max_pitched_bandwidth,
+                    seed: rng.next_u64(),
+                }
+            }
+        }
+    }
+}
+
+/// Distribution over unison stacks, i.e. several detuned copies of the same oscillator playing the same note.
+///
+/// Voices are spread evenly over the detune range, so the outermost voices are `detune / 2` cents above and below the note.
+/// The output is mono, so the spread is over phase rather than stereo position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnisonDistribution {
+    num_voices_distribution: UniformCount,
+    detune_distribution: UniformF,
+    phase_spread_distribution: UniformF,
+}
+
+impl UnisonDistribution {
+    /// `num_voices_range` is inclusive. `detune_range` is the total spread in cents and `phase_spread_range` is given in periods.
+    pub fn new(
+        num_voices_range: (usize, usize),
+        detune_range: (f32, f32),
+        phase_spread_range: (f32, f32),
+    ) -> Result<Self, ConfigError> {
+        let unison_distribution = Self {
+            num_voices_distribution: UniformCount::new(num_voices_range.0, num_voices_range.1),
+            detune_distribution: UniformF::new(detune_range.0, detune_range.1),
+            phase_spread_distribution: UniformF::new(phase_spread_range.0, phase_spread_range.1),
+        };
+        unison_distribution.validate()?;
+        Ok(unison_distribution)
+    }
+}
+
+impl UnisonDistribution {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        let (min_voices, max_voices) = self.num_voices_distribution.range();
+        violations.check(1 <= min_voices && min_voices <= max_voices, || {
+            ConfigError::InvalidRange {
+                name: "unison voice count",
+                range: (min_voices as f64, max_voices as f64),
+                requirement: "there must be at least one voice and the range must be non-empty",
+            }
+        });
+        violations.check_range(
+            "unison detune",
+            self.detune_distribution.range(),
+            (0., f32::MAX),
+            "the range must be finite, non-negative and non-empty",
+        );
+        violations.check_range(
+            "unison phase spread",
+            self.phase_spread_distribution.range(),
+            (0., 1.),
+            "the range must be within [0;1]",
+        );
+        violations.into_result()
+    }
+}
+
+impl Distribution<UnisonParameters> for UnisonDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> UnisonParameters {
+        UnisonParameters {
+            num_voices: self.num_voices_distribution.sample(rng),
+            detune: self.detune_distribution.sample(rng),
+            phase_spread: self.phase_spread_distribution.sample(rng),
+            seed: rng.next_u64(),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct UnisonParameters {
+    num_voices: usize,
+    detune: f32,
+    phase_spread: f32,
+    seed: u64,
+}
+
+impl UnisonParameters {
+    pub fn num_voices(&self) -> usize {
+        self.num_voices
+    }
+
+    /// Total detune spread in cents.
+    pub fn detune(&self) -> f32 {
+        self.detune
+    }
+
+    /// Maximum phase offset between voices in periods.
+    pub fn phase_spread(&self) -> f32 {
+        self.phase_spread
+    }
+
+    /// Returns the detune in cents and the phase offset in periods of each voice.
+    pub fn voices(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let mut rng = Pcg64Mcg::seed_from_u64(self.seed);
+        (0..self.num_voices).map(move |voice| {
+            let detune = if self.num_voices > 1 {
+                self.detune * (voice as f32 / (self.num_voices - 1) as f32 - 0.5)
+            } else {
+                0.
+            };
+            let phase = if voice == 0 {
+                0.
+            } else {
+                rng.gen_range(0f32..=1.) * self.phase_spread
+            };
+            (detune, phase)
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OscillatorDistribution {
     oscillator_type_distribution: OscillatorTypeDistribution,
     probability: f64,
     amplitude_distribution: LogUniform,
+    #[serde(default)]
+    unison_distribution: Option<UnisonDistribution>,
 }
 
 impl OscillatorDistribution {
@@ -62,26 +235,27 @@ impl OscillatorDistribution {
         oscillator_type_distribution: OscillatorTypeDistribution,
         probability: f64,
         amplitude_range: (f32, f32),
-    ) -> Self {
-        assert!(
-            amplitude_range.0 >= 0.0,
-            "Amplitude range must be non-negative."
-        );
-        assert!(
-            amplitude_range.1 >= amplitude_range.0,
-            "Amplitude range must be non-empty."
-        );
-        assert!(
-            amplitude_range.1 <= 1.0,
-            "Amplitude range must be less than 1."
-        );
-        assert!(probability > 0.0, "Probability must be positive.");
-        assert!(probability <= 1.0, "Probability must be less than 1.");
-        Self {
+    ) -> Result<Self, ConfigError> {
+        let oscillator = Self {
             oscillator_type_distribution,
             probability,
-            amplitude_distribution: LogUniform::from_tuple(amplitude_range),
+            amplitude_distribution: LogUniform::from_valid_tuple(amplitude_range),
+            unison_distribution: None,
+        };
+        oscillator.validate()?;
+        Ok(oscillator)
+    }
+
+    /// Fails unless the oscillator has a frequency.
+    pub fn with_unison(
+        mut self,
+        unison_distribution: UnisonDistribution,
+    ) -> Result<Self, ConfigError> {
+        if !self.has_frequency() {
+            return Err(ConfigError::UnpitchedUnison);
         }
+        self.unison_distribution = Some(unison_distribution);
+        Ok(self)
     }
 
     pub fn maximum_amplitude(&self) -> f32 {
@@ -91,6 +265,58 @@ impl OscillatorDistribution {
     pub fn has_frequency(&self) -> bool {
         self.oscillator_type_distribution.has_frequency()
     }
+
+    /// Checks the probability, the amplitude range, the noise bandwidth range and the unison distribution.
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check_probability("oscillator probability", self.probability, false);
+        violations.add(self.amplitude_distribution.validate("amplitude"));
+        violations.check(self.amplitude_distribution.max() <= 1.0, || {
+            ConfigError::OutOfRange {
+                name: "maximum amplitude",
+                value: self.amplitude_distribution.max() as f64,
+                interval: "[0;1]",
+            }
+        });
+        if let OscillatorTypeDistribution::NoiseBand {
+            bandwidth_distribution,
+            ..
+        } = &self.oscillator_type_distribution
+        {
+            violations.add(bandwidth_distribution.validate("bandwidth"));
+        }
+        if let Some(unison_distribution) = &self.unison_distribution {
+            violations.add(unison_distribution.validate());
+            violations.check(self.has_frequency(), || ConfigError::UnpitchedUnison);
+        }
+        violations.into_result()
+    }
+
+    /// Checks that the wave forms, wavetables or instruments the oscillator samples from are loaded.
+    pub(crate) fn check_banks(&self, wave_forms: &WaveForms) -> Result<(), ConfigError> {
+        let (kind, indices, available) = match &self.oscillator_type_distribution {
+            OscillatorTypeDistribution::Custom(indices) => {
+                ("wave form", indices, wave_forms.num_wave_forms())
+            }
+            OscillatorTypeDistribution::Wavetable(indices, _) => {
+                ("wavetable", indices, wave_forms.num_wavetables())
+            }
+            OscillatorTypeDistribution::Sampler(indices) => {
+                ("instrument", indices, wave_forms.num_instruments())
+            }
+            _ => return Ok(()),
+        };
+        let (min, max) = indices.range();
+        if min >= max || max > available {
+            Err(ConfigError::MissingBank {
+                kind,
+                index: max.max(min + 1) - 1,
+                available,
+            })
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl Distribution<Option<OscillatorParameters>> for OscillatorDistribution {
@@ -99,6 +325,10 @@ impl Distribution<Option<OscillatorParameters>> for OscillatorDistribution {
             .then(|| OscillatorParameters {
                 oscillator_type: self.oscillator_type_distribution.sample(rng),
                 amplitude: self.amplitude_distribution.sample(rng),
+                unison: self
+                    .unison_distribution
+                    .as_ref()
+                    .map(|unison_distribution| unison_distribution.sample(rng)),
             })
     }
 }
@@ -110,49 +340,179 @@ pub enum OscillatorType {
     Pulse(f32),
     Triangle,
     Custom(usize, u64),
-    // Contains the seed for the noise generator.
-    Noise(u64),
+    /// Morph contains the positions at the start and end of the clip. The seed determines the starting phase.
+    Wavetable {
+        wavetable: usize,
+        morph: (f32, f32),
+        seed: u64,
+    },
+    /// Contains the instrument index.
+    Sampler(usize),
+    // Contains the color and the seed for the noise generator.
+    Noise(NoiseColor, u64),
+    /// Bandwidth is in cents.
+    NoiseBand {
+        bandwidth: f32,
+        pitched: bool,
+        seed: u64,
+    },
+}
+
+/// Frequency input for the library oscillators that plays back a precomputed trajectory, one value per sample.
+///
+/// Samples before `offset` are the pre-roll that shifts the phase and use the initial frequency.
+#[derive(Clone)]
+struct FrequencyTrajectory {
+    frequencies: Arc<[f32]>,
+    offset: u64,
+}
+
+impl FrequencyTrajectory {
+    fn new(frequencies: &[f32], offset: u64) -> Self {
+        Self {
+            frequencies: frequencies.into(),
+            offset,
+        }
+    }
+}
+
+impl Module for FrequencyTrajectory {
+    fn next(&mut self, sample_num: u64) -> f32 {
+        let index = sample_num.saturating_sub(self.offset) as usize;
+        self.frequencies[index.min(self.frequencies.len() - 1)]
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct OscillatorParameters {
     oscillator_type: OscillatorType,
     amplitude: f32,
+    unison: Option<UnisonParameters>,
 }
 
 impl OscillatorParameters {
-    fn write_oscillator(mut oscillator: impl Module, amplitude: f32, buffer: &mut [f32]) {
+    /// Writes the oscillator to the buffer after discarding the first `offset` samples, which shifts its phase.
+    fn write_oscillator(
+        mut oscillator: impl Module,
+        amplitude: f32,
+        offset: u64,
+        buffer: &mut [f32],
+    ) {
+        for sample_num in 0..offset {
+            oscillator.next(sample_num);
+        }
         for (sample_num, sample) in buffer.iter_mut().enumerate() {
-            *sample += oscillator.next(sample_num as u64) * amplitude;
+            *sample += oscillator.next(sample_num as u64 + offset) * amplitude;
+        }
+    }
+
+    /// Seed for the drift of a unison voice. Each voice drifts independently, which is where most of the beating
+    /// comes from.
+    fn voice_drift_seed(drift_seed: u64, voice: usize) -> u64 {
+        if voice == 0 {
+            drift_seed
+        } else {
+            crate::hash(drift_seed.wrapping_add(voice as u64))
         }
     }
 
+    /// Detune in cents, drift seed and amplitude of each voice, where `drift_seed` seeds the drift of the first voice.
+    pub fn voice_drifts(&self, drift_seed: u64) -> Vec<(f32, u64, f32)> {
+        match &self.unison {
+            Some(unison) => {
+                let amplitude = self.amplitude / unison.num_voices() as f32;
+                unison
+                    .voices()
+                    .enumerate()
+                    .map(|(voice, (detune, _))| {
+                        (detune, Self::voice_drift_seed(drift_seed, voice), amplitude)
+                    })
+                    .collect()
+            }
+            None => vec![(0., drift_seed, self.amplitude)],
+        }
+    }
+
+    /// Writes the oscillator to the buffer.
+    ///
+    /// `drift_seed` seeds the drift of the first voice and `pitch_factors` holds a frequency multiplier for each sample which is used for vibrato.
+    #[allow(clippy::too_many_arguments)]
     pub fn write(
         &self,
         frequency: f32,
-        frequency_std_dev: f32,
-        frequency_random_walk_seed: u64,
+        drift: &DriftParameters,
+        drift_seed: u64,
+        pitch_factors: &[f32],
         wave_forms: &WaveForms,
         sample_rate: u32,
         buffer: &mut [f32],
     ) {
-        let amplitude = self.amplitude;
-
-        let frequency_walk_dampening = 0.9;
-        let rng = Pcg64Mcg::seed_from_u64(frequency_random_walk_seed);
-        let walk_std_dev = frequency * ((2f32).powf(frequency_std_dev / 1200.) - 1.);
-        let frequency_module =
-            RandomWalk::new(rng, walk_std_dev, frequency_walk_dampening, sample_rate) + frequency;
+        let frequency_trajectory = |frequency: f32, seed: u64| -> Vec<f32> {
+            drift
+                .pitch_factors(seed, sample_rate, pitch_factors.len())
+                .into_iter()
+                .zip(pitch_factors)
+                .map(|(drift_factor, pitch_factor)| frequency * drift_factor * pitch_factor)
+                .collect()
+        };
+        match &self.unison {
+            Some(unison) => {
+                let amplitude = self.amplitude / unison.num_voices() as f32;
+                for (voice, (detune, phase)) in unison.voices().enumerate() {
+                    let seed = Self::voice_drift_seed(drift_seed, voice);
+                    let frequencies =
+                        frequency_trajectory(frequency * (detune / 1200.).exp2(), seed);
+                    self.write_voice(
+                        &frequencies,
+                        phase,
+                        amplitude,
+                        wave_forms,
+                        sample_rate,
+                        buffer,
+                    );
+                }
+            }
+            None => {
+                let frequencies = frequency_trajectory(frequency, drift_seed);
+                self.write_voice(
+                    &frequencies,
+                    0.,
+                    self.amplitude,
+                    wave_forms,
+                    sample_rate,
+                    buffer,
+                )
+            }
+        }
+    }
 
+    fn write_voice(
+        &self,
+        frequencies: &[f32],
+        phase: f32,
+        amplitude: f32,
+        wave_forms: &WaveForms,
+        sample_rate: u32,
+        buffer: &mut [f32],
+    ) {
+        if frequencies.is_empty() {
+            return;
+        }
+        // The library oscillators follow the frequency trajectory, which carries drift and vibrato, and stay
+        // band-limited. The phase is shifted by discarding samples at the initial frequency.
+        let offset = (phase / frequencies[0] * sample_rate as f32) as u64;
+        let frequency_module = FrequencyTrajectory::new(frequencies, offset);
         match self.oscillator_type {
             OscillatorType::Sine => Self::write_oscillator(
                 SineOscillator::new(frequency_module, sample_rate).module(),
                 amplitude,
+                offset,
                 buffer,
             ),
             OscillatorType::Saw => Self::write_oscillator(
                 SawOscillator::new(frequency_module, sample_rate).module(),
                 amplitude,
+                offset,
                 buffer,
             ),
             OscillatorType::Pulse(duty_cycle) => Self::write_oscillator(
@@ -160,29 +520,76 @@ impl OscillatorParameters {
                     + -(duty_cycle * 2. - 1.))
                     .module(),
                 amplitude,
+                offset,
                 buffer,
             ),
             OscillatorType::Triangle => Self::write_oscillator(
                 TriangleOscillator::new(frequency_module, sample_rate).module(),
                 amplitude,
+                offset,
                 buffer,
             ),
             OscillatorType::Custom(wave_form, seed) => Self::write_oscillator(
                 CustomOscillator::new(
                     frequency_module,
                     wave_forms.get(wave_form),
-                    Pcg64Mcg::seed_from_u64(seed).gen_range(0f32..1.),
+                    (Pcg64Mcg::seed_from_u64(seed).gen_range(0f32..1.) + phase).fract(),
                     sample_rate,
                 )
                 .module(),
                 amplitude,
+                0,
                 buffer,
             ),
-            OscillatorType::Noise(seed) => Self::write_oscillator(
+            OscillatorType::Wavetable {
+                wavetable,
+                morph: (morph_start, morph_end),
+                seed,
+            } => {
+                let wavetable = wave_forms.wavetable(wavetable);
+                let mut phase = (Pcg64Mcg::seed_from_u64(seed).gen_range(0f32..1.) + phase).fract();
+                let inverse_sample_rate = 1. / sample_rate as f32;
+                let num_samples = buffer.len();
+                for (sample_num, (sample, &frequency)) in
+                    buffer.iter_mut().zip(frequencies).enumerate()
+                {
+                    let progress = sample_num as f32 / num_samples as f32;
+                    let morph = morph_start + (morph_end - morph_start) * progress;
+                    *sample += wavetable.value(phase, morph, frequency, sample_rate) * amplitude;
+                    phase = (phase + frequency * inverse_sample_rate).rem_euclid(1.);
+                }
+            }
+            OscillatorType::Sampler(instrument) => {
+                // The recording closest in pitch is resampled to follow the frequency trajectory,
+                // which keeps its natural attack and decay.
+                let note = wave_forms.instrument(instrument).nearest(frequencies[0]);
+                let step_factor = note.sample_rate() as f32 / sample_rate as f32 / note.frequency();
+                let mut position = phase * note.sample_rate() as f32 / note.frequency();
+                for (sample, &frequency) in buffer.iter_mut().zip(frequencies) {
+                    *sample += note.value(position) * amplitude;
+                    position += frequency * step_factor;
+                }
+            }
+            OscillatorType::Noise(NoiseColor::White, seed) => Self::write_oscillator(
                 NoiseOscillator::new(Pcg64Mcg::seed_from_u64(seed)).module(),
                 amplitude,
+                0,
                 buffer,
             ),
+            OscillatorType::Noise(color, seed) => {
+                let noise = noise::colored_noise(color, seed, buffer.len());
+                for (sample, noise) in buffer.iter_mut().zip(noise) {
+                    *sample += noise * amplitude;
+                }
+            }
+            OscillatorType::NoiseBand {
+                bandwidth, seed, ..
+            } => {
+                let noise = noise::noise_band(frequencies, bandwidth, seed, sample_rate);
+                for (sample, noise) in buffer.iter_mut().zip(noise) {
+                    *sample += noise * amplitude;
+                }
+            }
         }
     }
 
@@ -190,6 +597,10 @@ impl OscillatorParameters {
         self.amplitude
     }
 
+    pub fn unison(&self) -> Option<&UnisonParameters> {
+        self.unison.as_ref()
+    }
+
     pub fn has_frequency(&self) -> bool {
         self.amplitude > 0.0
             && match self.oscillator_type {
@@ -198,7 +609,85 @@ impl OscillatorParameters {
                 OscillatorType::Pulse(_) => true,
                 OscillatorType::Triangle => true,
                 OscillatorType::Custom(_, _) => true,
-                OscillatorType::Noise(_) => false,
+                OscillatorType::Wavetable { .. } => true,
+                OscillatorType::Sampler(_) => true,
+                OscillatorType::Noise(_, _) => false,
+                OscillatorType::NoiseBand { pitched, .. } => pitched,
             }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::TAU;
+
+    use rand::prelude::Distribution;
+    use rand_pcg::Pcg64Mcg;
+
+    use super::{OscillatorParameters, OscillatorType, UnisonParameters};
+    use crate::parameters::{
+        drift::{DriftDistribution, DriftModelDistribution},
+        WaveForms,
+    };
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn unison_voices_beat_against_each_other() {
+        // A wavetable of pure sines, so only the fundamental beats.
+        let sine: Vec<f32> = (0..64).map(|i| (i as f32 / 64. * TAU).sin()).collect();
+        let wave_forms: WaveForms = serde_json::from_value(serde_json::json!({
+            "wave_forms": [],
+            "wavetables": [[sine, sine]],
+            "instruments": [],
+        }))
+        .unwrap();
+        let drift = DriftDistribution::new(DriftModelDistribution::glide((0., 0.)))
+            .sample(&mut Pcg64Mcg::new(1));
+        let render = |unison| {
+            let oscillator = OscillatorParameters {
+                oscillator_type: OscillatorType::Wavetable {
+                    wavetable: 0,
+                    morph: (0., 0.),
+                    seed: 1,
+                },
+                amplitude: 0.5,
+                unison,
+            };
+            let num_samples = SAMPLE_RATE as usize / 2;
+            let mut buffer = vec![0.; num_samples];
+            oscillator.write(
+                440.,
+                &drift,
+                1,
+                &vec![1.; num_samples],
+                &wave_forms,
+                SAMPLE_RATE,
+                &mut buffer,
+            );
+            buffer
+        };
+        let single = render(None);
+        let stack = render(Some(UnisonParameters {
+            num_voices: 3,
+            detune: 20.,
+            phase_spread: 0.,
+            seed: 1,
+        }));
+
+        // The voices start in phase and the outer voices, 10 cents away from the note, are in antiphase with the
+        // center voice after half a beat period.
+        let window = SAMPLE_RATE as usize / 100;
+        let start = rms(&stack[..window]) / rms(&single[..window]);
+        assert!((start - 1.).abs() < 0.05, "Start: {start}");
+        let beat_frequency = 440. * ((10f32 / 1200.).exp2() - 1.);
+        let half_beat = (SAMPLE_RATE as f32 / beat_frequency / 2.) as usize;
+        let range = half_beat - window / 2..half_beat + window / 2;
+        let half_beat = rms(&stack[range.clone()]) / rms(&single[range]);
+        assert!((half_beat - 1. / 3.).abs() < 0.1, "Half beat: {half_beat}");
+    }
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use float_ord::FloatOrd;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    wave_form_extraction, Audio,
+};
+
+/// Name of the optional file in an instrument directory that maps file names to MIDI note numbers.
+pub const PITCHES_FILE_NAME: &str = "_pitches.json";
+
+/// A recording of a single note played by an instrument.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedNote {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    frequency: f32,
+}
+
+impl RecordedNote {
+    /// The recording is normalized to a peak of 1.
+    pub fn new(
+        mut samples: Vec<f32>,
+        sample_rate: u32,
+        frequency: f32,
+    ) -> Result<Self, ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(!samples.is_empty(), || {
+            ConfigError::Empty("recording samples")
+        });
+        violations.check(samples.iter().all(|x| x.is_finite()), || {
+            ConfigError::Invalid {
+                name: "recording",
+                requirement: "every sample must be finite",
+            }
+        });
+        violations.check(sample_rate > 0, || {
+            ConfigError::Zero("recording sample rate")
+        });
+        violations.check(frequency.is_finite() && frequency > 0., || {
+            ConfigError::OutOfRange {
+                name: "recording frequency",
+                value: frequency as f64,
+                interval: "(0;inf)",
+            }
+        });
+        violations.into_result()?;
+        let peak = samples.iter().map(|x| x.abs()).fold(0., f32::max);
+        if peak > 0. {
+            samples.iter_mut().for_each(|x| *x /= peak);
+        }
+        Ok(Self {
+            samples,
+            sample_rate,
+            frequency,
+        })
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Linearly interpolated value at a fractional position. Silent outside the recording.
+    pub fn value(&self, position: f32) -> f32 {
+        if position < 0. {
+            return 0.;
+        }
+        let index = position.floor() as usize;
+        let fraction = position - index as f32;
+        match (self.samples.get(index), self.samples.get(index + 1)) {
+            (Some(&current), Some(&next)) => current * (1. - fraction) + next * fraction,
+            (Some(&current), None) => current * (1. - fraction),
+            _ => 0.,
+        }
+    }
+}
+
+/// A set of recorded notes from one instrument.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SampledInstrument {
+    notes: Vec<RecordedNote>,
+}
+
+impl SampledInstrument {
+    pub fn new(notes: Vec<RecordedNote>) -> Self {
+        assert!(!notes.is_empty(), "Instrument must have at least one note.");
+        Self { notes }
+    }
+
+    /// Loads every WAV file in a directory as a note, in the order of their file names.
+    ///
+    /// The pitch of each file is taken from `_pitches.json` if it lists the file, otherwise from a note name
+    /// (e.g. `C4`, `F#3`, `Bb2`) or MIDI note number at the end of the file name, and otherwise estimated from the audio.
+    pub fn load_dir<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let pitches_path = path.join(PITCHES_FILE_NAME);
+        let pitches: HashMap<String, f32> = if pitches_path.exists() {
+            let pitches_file = fs::File::open(&pitches_path)?;
+            serde_json::from_reader(pitches_file)
+                .with_context(|| format!("Could not parse '{}'.", pitches_path.display()))?
+        } else {
+            HashMap::new()
+        };
+
+        let mut file_paths = fs::read_dir(path)
+            .context("Could not read directory.")?
+            .map(|entry| Ok(entry.context("Could not read directory entry.")?.path()))
+            .collect::<Result<Vec<_>>>()?;
+        file_paths.sort();
+
+        let mut notes = Vec::new();
+        for file_path in file_paths {
+            if !file_path
+                .extension()
+                .map(|ext| ext == "wav")
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            let audio = Audio::from_wav(&file_path)
+                .with_context(|| format!("Could not load '{}'.", file_path.display()))?;
+            if audio.samples.is_empty() {
+                bail!("'{}' contains no samples.", file_path.display());
+            }
+            let file_name = file_path.file_name().unwrap().to_string_lossy();
+            let stem = file_path.file_stem().unwrap().to_string_lossy();
+            let frequency = match pitches
+                .get(file_name.as_ref())
+                .copied()
+                .or_else(|| note_number_from_file_stem(&stem))
+            {
+                Some(note_number) => crate::note_number_to_frequency(note_number),
+                None => {
+                    wave_form_extraction::stable_region(&audio)
+                        .with_context(|| {
+                            format!("Could not determine pitch of '{}'.", file_path.display())
+                        })?
+                        .2
+                }
+            };
+            notes.push(RecordedNote::new(
+                audio.samples,
+                audio.sample_rate,
+                frequency,
+            )?);
+        }
+        if notes.is_empty() {
+            bail!("No WAV files found in '{}'.", path.display());
+        }
+        Ok(Self::new(notes))
+    }
+
+    /// The recording with the pitch closest to the given frequency.
+    pub fn nearest(&self, frequency: f32) -> &RecordedNote {
+        self.notes
+            .iter()
+            .min_by_key(|note| FloatOrd(crate::cent_diff(note.frequency, frequency).abs()))
+            .unwrap()
+    }
+
+    pub fn num_notes(&self) -> usize {
+        self.notes.len()
+    }
+}
+
+/// Parses the last token of a file stem, separated by `_`, `-` or space, as a note name or MIDI note number.
+///
+/// A `-` can also be the sign of a negative octave, so the longest suffix after one that parses is used.
+fn note_number_from_file_stem(stem: &str) -> Option<f32> {
+    let token = stem.rsplit(['_', ' ']).next()?;
+    std::iter::once(token)
+        .chain(
+            token
+                .match_indices('-')
+                .map(|(index, _)| &token[index + 1..]),
+        )
+        .find_map(|suffix| {
+            suffix
+                .parse::<u8>()
+                .ok()
+                .map(f32::from)
+                .or_else(|| crate::note_name_to_note_number(suffix))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::SampledInstrument;
+    use crate::Audio;
+
+    #[test]
+    fn instruments_are_loaded_in_file_name_order() {
+        let dir =
+            std::env::temp_dir().join(format!("audio_samples_instrument_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["piano_G4.wav", "piano_C4.wav", "piano_E4.wav"] {
+            Audio::from_samples(vec![0.5; 100], 1000)
+                .to_wav(dir.join(name))
+                .unwrap();
+        }
+        let instrument = SampledInstrument::load_dir(&dir).unwrap();
+        Audio::from_samples(Vec::new(), 1000)
+            .to_wav(dir.join("piano_A4.wav"))
+            .unwrap();
+        let empty = SampledInstrument::load_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let frequencies: Vec<f32> = instrument
+            .notes
+            .iter()
+            .map(|note| note.frequency())
+            .collect();
+        let expected = [60., 64., 67.].map(crate::note_number_to_frequency);
+        assert_eq!(frequencies, expected);
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn note_numbers_from_file_stems() {
+        let cases = [
+            ("piano_C4", Some(60.)),
+            ("strings-F#3", Some(54.)),
+            ("horn Bb2", Some(46.)),
+            ("bass_40", Some(40.)),
+            ("A0", Some(21.)),
+            ("organ-Bb-1", Some(10.)),
+            ("C-1", Some(0.)),
+            ("cello_loud", None),
+        ];
+        for (stem, expected) in cases {
+            assert_eq!(super::note_number_from_file_stem(stem), expected, "{stem}");
+        }
+    }
+}
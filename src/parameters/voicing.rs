@@ -0,0 +1,197 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chord::ChordType,
+    config::{ConfigError, Violations},
+};
+
+/// How the notes of a chord are spread over octaves after the inversion is applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VoicingStyle {
+    /// All notes within as small a range as possible.
+    Close,
+    /// Every second note from the bottom, not counting the lowest, is raised by an octave.
+    Open,
+    /// The second highest note of the close voicing is lowered by an octave. Chords with fewer than three notes
+    /// stay in close voicing.
+    Drop2,
+}
+
+/// Distribution over explicit chord voicings, which replace the random octaves of [`super::OctaveParameters`]
+/// for the notes of the chord. Voiced notes outside its frequency range are moved back into it by octaves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoicingDistribution {
+    inversion_probability: f64,
+    styles: Vec<VoicingStyle>,
+    bass_intervals: Vec<u32>,
+    bass_probability: f64,
+}
+
+impl VoicingDistribution {
+    /// With probability `inversion_probability` one of the notes above the root is chosen uniformly as the lowest
+    /// note. The style is chosen uniformly from `styles`.
+    pub fn new<A>(inversion_probability: f64, styles: A) -> Self
+    where
+        A: Into<Vec<VoicingStyle>>,
+    {
+        assert!(
+            (0. ..=1.).contains(&inversion_probability),
+            "Probability must be within [0;1]. Value: {inversion_probability}"
+        );
+        let styles = styles.into();
+        assert!(!styles.is_empty(), "No voicing styles provided.");
+        Self {
+            inversion_probability,
+            styles,
+            bass_intervals: Vec::new(),
+            bass_probability: 0.,
+        }
+    }
+
+    /// With the given probability, adds a bass note below the chord. Its interval above the root in semitones is
+    /// chosen uniformly from `intervals`, so slash chords such as C/D can be generated.
+    pub fn with_bass_note<A>(mut self, intervals: A, probability: f64) -> Self
+    where
+        A: Into<Vec<u32>>,
+    {
+        assert!(
+            (0. ..=1.).contains(&probability),
+            "Probability must be within [0;1]. Value: {probability}"
+        );
+        self.bass_intervals = intervals.into();
+        assert!(!self.bass_intervals.is_empty(), "No bass notes provided.");
+        self.bass_probability = probability;
+        self
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check_probability("inversion probability", self.inversion_probability, true);
+        violations.check(!self.styles.is_empty(), || {
+            ConfigError::Empty("voicing styles")
+        });
+        violations.check_probability("bass note probability", self.bass_probability, true);
+        violations.check(
+            self.bass_probability == 0. || !self.bass_intervals.is_empty(),
+            || ConfigError::Empty("bass note intervals"),
+        );
+        violations.into_result()
+    }
+
+    /// Samples the voicing of a chord with `num_notes` notes.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, num_notes: usize) -> VoicingParameters {
+        let inversion = if num_notes > 1 && rng.gen_bool(self.inversion_probability) {
+            rng.gen_range(1..num_notes)
+        } else {
+            0
+        };
+        VoicingParameters {
+            inversion,
+            style: *self.styles.choose(rng).unwrap(),
+            bass_interval: if self.bass_probability > 0. && rng.gen_bool(self.bass_probability) {
+                self.bass_intervals.choose(rng).copied()
+            } else {
+                None
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct VoicingParameters {
+    inversion: usize,
+    style: VoicingStyle,
+    bass_interval: Option<u32>,
+}
+
+impl VoicingParameters {
+    pub fn style(&self) -> VoicingStyle {
+        self.style
+    }
+
+    /// Interval of the added bass note above the root in semitones.
+    pub fn bass_interval(&self) -> Option<u32> {
+        self.bass_interval
+    }
+
+    /// Positions of the notes of the chord in semitones relative to the root, in the order of the chord, followed
+    /// by the bass note if there is one.
+    ///
+    /// The root keeps its position unless the voicing style moves it. Notes below the inverted note are kept, and
+    /// the inverted note and all notes above it are lowered by an octave.
+    pub fn positions(&self, chord: &ChordType) -> Vec<i32> {
+        let mut positions: Vec<i32> = std::iter::once(0)
+            .chain(chord.intervals().iter().map(|&interval| interval as i32))
+            .collect();
+        if self.inversion > 0 {
+            let lowest = positions[self.inversion];
+            for position in positions.iter_mut() {
+                if *position >= lowest {
+                    *position -= 12;
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..positions.len()).collect();
+        order.sort_by_key(|&index| positions[index]);
+        match self.style {
+            VoicingStyle::Close => {}
+            VoicingStyle::Open => {
+                for &index in order.iter().skip(1).step_by(2) {
+                    positions[index] += 12;
+                }
+            }
+            VoicingStyle::Drop2 => {
+                if order.len() >= 3 {
+                    positions[order[order.len() - 2]] -= 12;
+                }
+            }
+        }
+
+        if let Some(interval) = self.bass_interval {
+            let lowest = *positions.iter().min().unwrap();
+            let below = (lowest - interval as i32 - 1).rem_euclid(12) + 1;
+            positions.push(lowest - below);
+        }
+        positions
+    }
+}
+
+/// Index of the lowest note of the chord in the frequencies of a voicing, in the order of
+/// [`VoicingParameters::positions`] and not counting an added bass note.
+pub fn realized_inversion(frequencies: &[f32], num_notes: usize) -> usize {
+    (0..num_notes)
+        .min_by(|&a, &b| frequencies[a].total_cmp(&frequencies[b]))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{VoicingParameters, VoicingStyle};
+    use crate::chord::ChordType;
+
+    #[test]
+    fn inversions_and_styles() {
+        let seventh = ChordType::new([4, 7, 10]).unwrap();
+        let cases = [
+            (0, VoicingStyle::Close, None, vec![0, 4, 7, 10]),
+            (1, VoicingStyle::Close, None, vec![0, -8, -5, -2]),
+            (0, VoicingStyle::Open, None, vec![0, 16, 7, 22]),
+            (0, VoicingStyle::Drop2, None, vec![0, 4, -5, 10]),
+            (2, VoicingStyle::Close, Some(2), vec![0, 4, -5, -2, -10]),
+        ];
+        for (inversion, style, bass_interval, expected) in cases {
+            let voicing = VoicingParameters {
+                inversion,
+                style,
+                bass_interval,
+            };
+            assert_eq!(voicing.positions(&seventh), expected, "{voicing:?}");
+        }
+        assert_eq!(
+            super::realized_inversion(&[100., 126., 75., 178., 50.], 4),
+            2
+        );
+    }
+}
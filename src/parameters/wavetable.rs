@@ -0,0 +1,222 @@
+use rand::{prelude::Distribution, Rng};
+use rustfft::num_complex::Complex32;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ConfigError, Audio, UniformF};
+
+/// Number of samples in each frame of a wavetable.
+pub const TABLE_SIZE: usize = 2048;
+
+/// Number of harmonics in the most detailed version of each frame.
+const MAX_HARMONICS: usize = TABLE_SIZE / 2 - 1;
+
+/// A sequence of single cycle wave forms that can be morphed between.
+///
+/// Every frame is resampled to [`TABLE_SIZE`] and stored in one band-limited version per octave,
+/// where each version has half the harmonics of the previous one. Only the most detailed frames are serialized
+/// and the other versions are rebuilt from them when deserializing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Vec<f32>>", into = "Vec<Vec<f32>>")]
+pub struct Wavetable {
+    /// Indexed by octave and then by frame.
+    octaves: Vec<Vec<Vec<f32>>>,
+}
+
+impl Wavetable {
+    pub fn new<A>(wave_forms: &[A]) -> Self
+    where
+        A: AsRef<[f32]>,
+    {
+        assert!(
+            wave_forms.len() >= 2,
+            "A wavetable must have at least two wave forms."
+        );
+        let spectra: Vec<_> = wave_forms
+            .iter()
+            .map(|wave_form| Self::harmonics(wave_form.as_ref()))
+            .collect();
+        let num_octaves = (MAX_HARMONICS as f32).log2().ceil() as usize + 1;
+        let octaves = (0..num_octaves)
+            .map(|octave| {
+                let num_harmonics = MAX_HARMONICS >> octave;
+                spectra
+                    .iter()
+                    .map(|harmonics| Self::synthesize(harmonics, num_harmonics))
+                    .collect()
+            })
+            .collect();
+        Self { octaves }
+    }
+
+    /// Returns the DC offset and the amplitudes of the harmonics of a single cycle wave form normalized to its length.
+    fn harmonics(wave_form: &[f32]) -> Vec<Complex32> {
+        let spectrum = Audio::from_samples(wave_form.to_vec(), 1).fft();
+        let scale = 1. / wave_form.len() as f32;
+        spectrum
+            .into_iter()
+            .take(wave_form.len().div_ceil(2))
+            .map(|value| value * scale)
+            .collect()
+    }
+
+    /// Synthesizes a frame of [`TABLE_SIZE`] samples from the DC offset and at most `num_harmonics` harmonics.
+    fn synthesize(harmonics: &[Complex32], num_harmonics: usize) -> Vec<f32> {
+        let mut spectrum = vec![Complex32::new(0., 0.); TABLE_SIZE];
+        let scale = TABLE_SIZE as f32;
+        for (index, &value) in harmonics.iter().enumerate().take(num_harmonics + 1) {
+            spectrum[index] = value * scale;
+            if index > 0 {
+                spectrum[TABLE_SIZE - index] = value.conj() * scale;
+            }
+        }
+        Audio::from_spectrum(spectrum, 1).samples
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.octaves[0].len()
+    }
+
+    /// Index of the most detailed table with no harmonics above the Nyquist frequency.
+    fn octave(&self, frequency: f32, sample_rate: u32) -> usize {
+        let max_harmonics = sample_rate as f32 * 0.5 / frequency;
+        (0..self.octaves.len())
+            .find(|&octave| (MAX_HARMONICS >> octave) as f32 <= max_harmonics)
+            .unwrap_or(self.octaves.len() - 1)
+    }
+
+    /// Value of the wavetable at the given phase in `[0;1)` and morph position in `[0;1]`.
+    pub fn value(&self, phase: f32, morph: f32, frequency: f32, sample_rate: u32) -> f32 {
+        let frames = &self.octaves[self.octave(frequency, sample_rate)];
+        let position = morph.clamp(0., 1.) * (frames.len() - 1) as f32;
+        let frame = (position.floor() as usize).min(frames.len() - 2);
+        let fraction = position - frame as f32;
+        let value = |frame: &[f32]| {
+            let position = phase * TABLE_SIZE as f32;
+            let index = position.floor() as usize % TABLE_SIZE;
+            let next_index = (index + 1) % TABLE_SIZE;
+            let fraction = position.fract();
+            frame[index] * (1. - fraction) + frame[next_index] * fraction
+        };
+        value(&frames[frame]) * (1. - fraction) + value(&frames[frame + 1]) * fraction
+    }
+}
+
+impl TryFrom<Vec<Vec<f32>>> for Wavetable {
+    type Error = ConfigError;
+
+    fn try_from(frames: Vec<Vec<f32>>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 || frames.iter().any(|frame| frame.is_empty()) {
+            return Err(ConfigError::InvalidWavetable {
+                indices: (0..frames.len()).collect(),
+                num_wave_forms: frames.len(),
+            });
+        }
+        Ok(Self::new(&frames))
+    }
+}
+
+impl From<Wavetable> for Vec<Vec<f32>> {
+    fn from(wavetable: Wavetable) -> Self {
+        wavetable.octaves.into_iter().next().unwrap_or_default()
+    }
+}
+
+/// Distribution over how the morph position of a wavetable oscillator moves over a clip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MorphDistribution {
+    /// The morph position is fixed for the whole clip.
+    Static(UniformF),
+    /// The morph position moves linearly from a start to an end position over the clip.
+    Sweep { start: UniformF, end: UniformF },
+}
+
+impl MorphDistribution {
+    pub fn fixed(range: (f32, f32)) -> Self {
+        Self::assert_range(range);
+        Self::Static(UniformF::new(range.0, range.1))
+    }
+
+    pub fn sweep(start_range: (f32, f32), end_range: (f32, f32)) -> Self {
+        Self::assert_range(start_range);
+        Self::assert_range(end_range);
+        Self::Sweep {
+            start: UniformF::new(start_range.0, start_range.1),
+            end: UniformF::new(end_range.0, end_range.1),
+        }
+    }
+
+    fn assert_range(range: (f32, f32)) {
+        assert!(
+            0. <= range.0 && range.0 <= range.1 && range.1 <= 1.,
+            "Morph positions must be within [0;1]. Range: {range:?}"
+        );
+    }
+}
+
+impl Distribution<(f32, f32)> for MorphDistribution {
+    /// Samples the morph positions at the start and end of the clip.
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> (f32, f32) {
+        match self {
+            MorphDistribution::Static(distribution) => {
+                let position = distribution.sample(rng);
+                (position, position)
+            }
+            MorphDistribution::Sweep { start, end } => (start.sample(rng), end.sample(rng)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::TAU;
+
+    use super::Wavetable;
+
+    #[test]
+    fn resamples_and_band_limits() {
+        let sine: Vec<f32> = (0..100).map(|i| (i as f32 / 100. * TAU).sin()).collect();
+        let saw: Vec<f32> = (0..300).map(|i| i as f32 / 150. - 1.).collect();
+        let wavetable = Wavetable::new(&[sine, saw]);
+        assert_eq!(wavetable.num_frames(), 2);
+
+        for i in 0..16 {
+            let phase = i as f32 / 16.;
+            let expected = (phase * TAU).sin();
+            let value = wavetable.value(phase, 0., 100., 44100);
+            assert!(
+                (value - expected).abs() < 1e-3,
+                "Phase: {phase}  Value: {value}"
+            );
+
+            // Above a quarter of the sample rate only the fundamental of the saw is left.
+            let value = wavetable.value(phase, 1., 12000., 44100);
+            let fundamental = -(phase * TAU).sin() * 2. / std::f32::consts::PI;
+            assert!(
+                (value - fundamental).abs() < 2e-2,
+                "Phase: {phase}  Value: {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn mipmaps_are_rebuilt_when_deserializing() {
+        let sine: Vec<f32> = (0..100).map(|i| (i as f32 / 100. * TAU).sin()).collect();
+        let saw: Vec<f32> = (0..300).map(|i| i as f32 / 150. - 1.).collect();
+        let wavetable = Wavetable::new(&[sine, saw]);
+
+        let json = serde_json::to_string(&wavetable).unwrap();
+        let frames: Vec<Vec<f32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(frames.len(), 2);
+        let deserialized: Wavetable = serde_json::from_str(&json).unwrap();
+        for (octave, deserialized_octave) in wavetable.octaves.iter().zip(&deserialized.octaves) {
+            for (frame, deserialized_frame) in octave.iter().zip(deserialized_octave) {
+                for (value, deserialized_value) in frame.iter().zip(deserialized_frame) {
+                    assert!((value - deserialized_value).abs() < 1e-4);
+                }
+            }
+        }
+        assert_eq!(wavetable.octaves.len(), deserialized.octaves.len());
+
+        assert!(serde_json::from_str::<Wavetable>("[[0.0, 1.0]]").is_err());
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Number of MIDI notes a keyboard mapping can cover.
+const NUM_KEYS: u32 = 128;
+
+#[derive(Debug)]
+pub enum ScalaError {
+    Io(io::Error),
+    /// A line could not be parsed. Lines are counted from 1.
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    /// The file ended before all required values were read.
+    UnexpectedEnd,
+    /// The reference key of a keyboard mapping is not mapped to a scale degree.
+    UnmappedReference(u32),
+    /// Fewer than two keys are mapped, so note numbers cannot be interpolated between keys.
+    TooFewKeys(usize),
+    /// A deserialized scale has no pitches.
+    EmptyScale,
+}
+
+impl Display for ScalaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "Could not read Scala file: {error}"),
+            Self::Syntax { line, message } => write!(f, "Line {line}: {message}"),
+            Self::UnexpectedEnd => write!(f, "Scala file ended unexpectedly."),
+            Self::UnmappedReference(key) => {
+                write!(f, "Reference key {key} is not mapped to a scale degree.")
+            }
+            Self::TooFewKeys(num_keys) => write!(
+                f,
+                "At least two keys must be mapped to scale degrees. Mapped: {num_keys}"
+            ),
+            Self::EmptyScale => write!(f, "A scale must have at least one note."),
+        }
+    }
+}
+
+impl Error for ScalaError {}
+
+impl From<io::Error> for ScalaError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Lines of a Scala file without comments, numbered from 1.
+fn content_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.starts_with('!'))
+}
+
+fn syntax_error(line: usize, message: impl Into<String>) -> ScalaError {
+    ScalaError::Syntax {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Parses the first whitespace separated token of a line.
+fn parse_value<T: std::str::FromStr>(line: usize, text: &str) -> Result<T, ScalaError> {
+    let token = text.split_whitespace().next().unwrap_or("");
+    token
+        .parse()
+        .map_err(|_| syntax_error(line, format!("Could not parse '{token}'.")))
+}
+
+/// A scale from a Scala `.scl` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedScale")]
+pub struct Scale {
+    description: String,
+    /// Pitches of the degrees above the root in cents. The last pitch is the period of the scale, usually an octave.
+    pitches: Vec<f32>,
+}
+
+/// Fields of a [`Scale`] before it is checked to have at least one pitch.
+#[derive(Deserialize)]
+struct SerializedScale {
+    description: String,
+    pitches: Vec<f32>,
+}
+
+impl TryFrom<SerializedScale> for Scale {
+    type Error = ScalaError;
+
+    fn try_from(scale: SerializedScale) -> Result<Self, Self::Error> {
+        if scale.pitches.is_empty() {
+            return Err(ScalaError::EmptyScale);
+        }
+        Ok(Self {
+            description: scale.description,
+            pitches: scale.pitches,
+        })
+    }
+}
+
+impl Scale {
+    pub fn load<P>(path: P) -> Result<Self, ScalaError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScalaError> {
+        let mut lines = content_lines(text);
+        let (_, description) = lines.next().ok_or(ScalaError::UnexpectedEnd)?;
+        let (line, count) = lines.next().ok_or(ScalaError::UnexpectedEnd)?;
+        let count: usize = parse_value(line, count)?;
+        if count == 0 {
+            return Err(syntax_error(line, "A scale must have at least one note."));
+        }
+        let pitches = lines
+            .take(count)
+            .map(|(line, text)| Self::parse_pitch(line, text))
+            .collect::<Result<Vec<_>, _>>()?;
+        if pitches.len() < count {
+            return Err(ScalaError::UnexpectedEnd);
+        }
+        Ok(Self {
+            description: description.trim().to_owned(),
+            pitches,
+        })
+    }
+
+    /// Parses a pitch in cents if it contains a period and as a ratio or integer otherwise.
+    fn parse_pitch(line: usize, text: &str) -> Result<f32, ScalaError> {
+        let token = text.split_whitespace().next().unwrap_or("");
+        if token.contains('.') {
+            return parse_value(line, token);
+        }
+        let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+        let numerator: f64 = parse_value(line, numerator)?;
+        let denominator: f64 = parse_value(line, denominator)?;
+        if numerator <= 0. || denominator <= 0. {
+            return Err(syntax_error(line, format!("Invalid ratio '{token}'.")));
+        }
+        Ok((1200. * (numerator / denominator).log2()) as f32)
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Number of degrees in one period of the scale.
+    pub fn len(&self) -> usize {
+        self.pitches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pitches.is_empty()
+    }
+
+    /// Pitch of a scale degree above or below the root in cents, repeating the scale every period.
+    pub fn cents(&self, degree: i32) -> f32 {
+        let len = self.pitches.len() as i32;
+        let period = self.pitches[self.pitches.len() - 1];
+        let step = degree.rem_euclid(len);
+        let step_cents = if step == 0 {
+            0.
+        } else {
+            self.pitches[step as usize - 1]
+        };
+        degree.div_euclid(len) as f32 * period + step_cents
+    }
+
+    /// Frequency ratio of a scale degree to the root.
+    pub fn ratio(&self, degree: i32) -> f32 {
+        (self.cents(degree) / 1200.).exp2()
+    }
+}
+
+/// A mapping of MIDI notes to scale degrees from a Scala `.kbm` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardMapping {
+    first_key: u32,
+    last_key: u32,
+    /// Key that the root of the scale is mapped to.
+    middle_key: u32,
+    reference_key: u32,
+    reference_frequency: f32,
+    /// Scale degree reached after one repetition of the mapping.
+    octave_degree: i32,
+    /// Scale degree of each key in one repetition of the mapping. Empty for a linear mapping.
+    mapping: Vec<Option<i32>>,
+}
+
+impl KeyboardMapping {
+    /// Maps consecutive keys to consecutive scale degrees with the root on `middle_key`.
+    pub fn linear(middle_key: u32, reference_key: u32, reference_frequency: f32) -> Self {
+        assert!(
+            reference_frequency > 0.,
+            "Reference frequency must be positive."
+        );
+        Self {
+            first_key: 0,
+            last_key: NUM_KEYS - 1,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree: 0,
+            mapping: Vec::new(),
+        }
+    }
+
+    pub fn load<P>(path: P) -> Result<Self, ScalaError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ScalaError> {
+        let mut lines = content_lines(text);
+        let mut next_value = || lines.next().ok_or(ScalaError::UnexpectedEnd);
+
+        let (line, map_size) = next_value()?;
+        let map_size: usize = parse_value(line, map_size)?;
+        let (line, first_key) = next_value()?;
+        let first_key = parse_value(line, first_key)?;
+        let (line, last_key) = next_value()?;
+        let last_key = parse_value(line, last_key)?;
+        let (line, middle_key) = next_value()?;
+        let middle_key = parse_value(line, middle_key)?;
+        let (line, reference_key) = next_value()?;
+        let reference_key = parse_value(line, reference_key)?;
+        let (line, reference_frequency) = next_value()?;
+        let reference_frequency: f32 = parse_value(line, reference_frequency)?;
+        if reference_frequency <= 0. {
+            return Err(syntax_error(line, "Reference frequency must be positive."));
+        }
+        let (line, octave_degree) = next_value()?;
+        let octave_degree = parse_value(line, octave_degree)?;
+
+        // Missing entries at the end of the mapping are unmapped.
+        let mut mapping = vec![None; map_size];
+        for (entry, (line, text)) in mapping.iter_mut().zip(lines) {
+            let token = text.split_whitespace().next().unwrap_or("");
+            if token != "x" {
+                *entry = Some(parse_value(line, token)?);
+            }
+        }
+
+        let keyboard_mapping = Self {
+            first_key,
+            last_key,
+            middle_key,
+            reference_key,
+            reference_frequency,
+            octave_degree,
+            mapping,
+        };
+        if keyboard_mapping.degree(reference_key).is_none() {
+            return Err(ScalaError::UnmappedReference(reference_key));
+        }
+        Ok(keyboard_mapping)
+    }
+
+    /// Scale degree of a key, if it is mapped.
+    pub fn degree(&self, key: u32) -> Option<i32> {
+        if key < self.first_key || key > self.last_key {
+            return None;
+        }
+        let offset = key as i32 - self.middle_key as i32;
+        if self.mapping.is_empty() {
+            return Some(offset);
+        }
+        let size = self.mapping.len() as i32;
+        let entry = self.mapping[offset.rem_euclid(size) as usize]?;
+        Some(entry + offset.div_euclid(size) * self.octave_degree)
+    }
+}
+
+/// A Scala scale played through a keyboard mapping.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "SerializedScalaTuning")]
+pub struct ScalaTuning {
+    scale: Scale,
+    keyboard_mapping: KeyboardMapping,
+    /// Mapped keys and their frequencies in ascending key order, computed once from the scale and the mapping.
+    #[serde(skip)]
+    keys: Vec<(u32, f32)>,
+}
+
+/// Fields of a [`ScalaTuning`] as they are serialized, without the mapped keys.
+#[derive(Deserialize)]
+struct SerializedScalaTuning {
+    scale: Scale,
+    keyboard_mapping: KeyboardMapping,
+}
+
+impl TryFrom<SerializedScalaTuning> for ScalaTuning {
+    type Error = ScalaError;
+
+    fn try_from(tuning: SerializedScalaTuning) -> Result<Self, Self::Error> {
+        Self::new(tuning.scale, tuning.keyboard_mapping)
+    }
+}
+
+impl ScalaTuning {
+    /// Fails if the reference key of the mapping is not mapped or fewer than two keys are mapped.
+    pub fn new(scale: Scale, keyboard_mapping: KeyboardMapping) -> Result<Self, ScalaError> {
+        let mut tuning = Self {
+            scale,
+            keyboard_mapping,
+            keys: Vec::new(),
+        };
+        let mapping = &tuning.keyboard_mapping;
+        if mapping.degree(mapping.reference_key).is_none() {
+            return Err(ScalaError::UnmappedReference(mapping.reference_key));
+        }
+        tuning.keys = (0..NUM_KEYS)
+            .filter_map(|key| Some((key, tuning.key_frequency(key)?)))
+            .collect();
+        tuning.validate()?;
+        Ok(tuning)
+    }
+
+    /// Checks that enough keys are mapped to interpolate between them.
+    pub(crate) fn validate(&self) -> Result<(), ScalaError> {
+        let num_keys = self.keys.len();
+        if num_keys < 2 {
+            return Err(ScalaError::TooFewKeys(num_keys));
+        }
+        Ok(())
+    }
+
+    /// Loads a scale and an optional keyboard mapping. Without a mapping, the root of the scale is on middle C
+    /// and A4 is 440 Hz.
+    pub fn load<P, Q>(scale_path: P, keyboard_mapping_path: Option<Q>) -> Result<Self, ScalaError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let scale = Scale::load(scale_path)?;
+        let keyboard_mapping = match keyboard_mapping_path {
+            Some(path) => KeyboardMapping::load(path)?,
+            None => KeyboardMapping::linear(60, 69, crate::A4_FREQUENCY),
+        };
+        Self::new(scale, keyboard_mapping)
+    }
+
+    pub fn scale(&self) -> &Scale {
+        &self.scale
+    }
+
+    pub fn keyboard_mapping(&self) -> &KeyboardMapping {
+        &self.keyboard_mapping
+    }
+
+    /// Frequency of a key, if it is mapped.
+    pub fn key_frequency(&self, key: u32) -> Option<f32> {
+        let mapping = &self.keyboard_mapping;
+        let degree = mapping.degree(key)?;
+        let reference_degree = mapping.degree(mapping.reference_key).unwrap();
+        let cents = self.scale.cents(degree) - self.scale.cents(reference_degree);
+        Some(mapping.reference_frequency * (cents / 1200.).exp2())
+    }
+
+    /// Scale degree of a key within one period of the scale, with the root as 0, if the key is mapped.
+    pub fn pitch_class(&self, key: u32) -> Option<u32> {
+        let degree = self.keyboard_mapping.degree(key)?;
+        Some(degree.rem_euclid(self.scale.len() as i32) as u32)
+    }
+
+    /// Frequency of a fractional note number, interpolated logarithmically between the neighbouring mapped keys.
+    pub fn note_number_to_frequency(&self, note_number: f32) -> f32 {
+        let keys = &self.keys;
+        let index = keys
+            .iter()
+            .position(|&(key, _)| key as f32 > note_number)
+            .unwrap_or(keys.len() - 1)
+            .clamp(1, keys.len() - 1);
+        let ((low_key, low), (high_key, high)) = (keys[index - 1], keys[index]);
+        let fraction = (note_number - low_key as f32) / (high_key - low_key) as f32;
+        low * (high / low).powf(fraction)
+    }
+
+    /// Fractional note number of a frequency, interpolated logarithmically between the neighbouring mapped keys.
+    pub fn frequency_to_note_number(&self, frequency: f32) -> f32 {
+        let keys = &self.keys;
+        let index = keys
+            .iter()
+            .position(|&(_, key_frequency)| key_frequency > frequency)
+            .unwrap_or(keys.len() - 1)
+            .clamp(1, keys.len() - 1);
+        let ((low_key, low), (high_key, high)) = (keys[index - 1], keys[index]);
+        let fraction = (frequency / low).log2() / (high / low).log2();
+        low_key as f32 + fraction * (high_key - low_key) as f32
+    }
+
+    /// Frequency of the mapped key closest in pitch to the given frequency.
+    pub fn nearest_key_frequency(&self, frequency: f32) -> f32 {
+        self.keys
+            .iter()
+            .map(|&(_, key_frequency)| key_frequency)
+            .min_by(|a, b| {
+                crate::cent_diff(*a, frequency)
+                    .abs()
+                    .total_cmp(&crate::cent_diff(*b, frequency).abs())
+            })
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KeyboardMapping, ScalaTuning, Scale};
+
+    const PENTATONIC: &str = "! pentatonic.scl
+!
+Just pentatonic
+ 5
+!
+ 9/8
+ 5/4
+ 3/2
+ 5/3
+ 2/1
+";
+
+    #[test]
+    fn parses_scale_and_mapping() {
+        let scale = Scale::parse(PENTATONIC).unwrap();
+        assert_eq!(scale.description(), "Just pentatonic");
+        assert_eq!(scale.len(), 5);
+        assert!((scale.ratio(2) - 1.25).abs() < 1e-6);
+        assert!((scale.ratio(-1) - 5. / 6.).abs() < 1e-6);
+
+        // Maps the white keys C, D, E, G and A to the scale and leaves F and B unmapped.
+        let mapping = KeyboardMapping::parse(
+            "! white_keys.kbm\n12\n0\n127\n60\n69\n440.0\n5\n0\nx\n1\nx\n2\nx\nx\n3\nx\n4\nx\nx\n",
+        )
+        .unwrap();
+        assert_eq!(mapping.degree(62), Some(1));
+        assert_eq!(mapping.degree(65), None);
+        assert_eq!(mapping.degree(72), Some(5));
+
+        let tuning = ScalaTuning::new(scale, mapping).unwrap();
+        let c4 = 440. * 3. / 5.;
+        assert!((tuning.key_frequency(60).unwrap() - c4).abs() < 1e-3);
+        assert!((tuning.key_frequency(67).unwrap() - c4 * 1.5).abs() < 1e-3);
+        assert!((tuning.frequency_to_note_number(c4 * 1.5) - 67.).abs() < 1e-3);
+        assert!((tuning.note_number_to_frequency(72.) - c4 * 2.).abs() < 1e-3);
+        assert!((tuning.nearest_key_frequency(c4 * 1.4) - c4 * 1.5).abs() < 1e-3);
+        assert_eq!(tuning.pitch_class(67), Some(3));
+        assert_eq!(tuning.pitch_class(72), Some(0));
+        assert_eq!(tuning.pitch_class(65), None);
+    }
+
+    #[test]
+    fn rejects_mappings_with_a_single_key() {
+        let scale = Scale::parse(PENTATONIC).unwrap();
+        let mapping = KeyboardMapping::parse("1\n69\n69\n69\n69\n440.0\n1\n0\n").unwrap();
+        assert!(matches!(
+            ScalaTuning::new(scale, mapping),
+            Err(super::ScalaError::TooFewKeys(1))
+        ));
+    }
+
+    #[test]
+    fn deserialized_tunings_are_checked() {
+        let tuning = ScalaTuning::new(
+            Scale::parse(PENTATONIC).unwrap(),
+            KeyboardMapping::linear(60, 69, 440.),
+        )
+        .unwrap();
+        let mut value = serde_json::to_value(&tuning).unwrap();
+        assert_eq!(
+            serde_json::from_value::<ScalaTuning>(value.clone()).unwrap(),
+            tuning
+        );
+
+        value["scale"]["pitches"] = serde_json::json!([]);
+        assert!(serde_json::from_value::<ScalaTuning>(value).is_err());
+    }
+}
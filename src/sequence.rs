@@ -0,0 +1,429 @@
+use rand::{prelude::Distribution, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, Violations},
+    data::{DataPoint, DataPointLabel},
+    frames::{FrameLabels, Framing},
+    hash,
+    midi::MidiFile,
+    parameters::{DataParameters, DataPointParameters},
+    theory::ProgressionDistribution,
+    Audio, UniformCount, UniformF,
+};
+
+/// Length of the linear fade in and out of every event in seconds, which avoids clicks at onsets and offsets.
+const FADE_TIME: f32 = 0.005;
+
+/// Distribution over clips containing several timed events, each a note or chord sampled from [`DataParameters`].
+///
+/// All events of a clip share the oscillators and effects of the clip, so a clip sounds like one instrument.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceParameters {
+    num_events_distribution: UniformCount,
+    duration_distribution: UniformF,
+    onset_interval_distribution: UniformF,
+    progression: Option<ProgressionDistribution>,
+    num_samples: u64,
+}
+
+impl SequenceParameters {
+    /// `duration_range` is the length of each event and `onset_interval_range` the time from one onset to the
+    /// next in seconds. Events overlap when the interval is shorter than the duration and leave a rest when it is
+    /// longer. Events that would start after the end of the clip are left out.
+    pub fn new(
+        num_events_range: (usize, usize),
+        duration_range: (f32, f32),
+        onset_interval_range: (f32, f32),
+        num_samples: u64,
+    ) -> Result<Self, ConfigError> {
+        let sequence_parameters = Self {
+            num_events_distribution: UniformCount::new(num_events_range.0, num_events_range.1),
+            duration_distribution: UniformF::new(duration_range.0, duration_range.1),
+            onset_interval_distribution: UniformF::new(
+                onset_interval_range.0,
+                onset_interval_range.1,
+            ),
+            progression: None,
+            num_samples,
+        };
+        sequence_parameters.validate()?;
+        Ok(sequence_parameters)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        let (min_events, max_events) = self.num_events_distribution.range();
+        violations.check(0 < min_events && min_events <= max_events, || {
+            ConfigError::InvalidRange {
+                name: "number of events",
+                range: (min_events as f64, max_events as f64),
+                requirement: "there must be at least one event and the range must be non-empty",
+            }
+        });
+        violations.check_range(
+            "event duration",
+            self.duration_distribution.range(),
+            (f32::MIN_POSITIVE, f32::MAX),
+            "the range must be positive and non-empty",
+        );
+        violations.check_range(
+            "onset interval",
+            self.onset_interval_distribution.range(),
+            (0., f32::MAX),
+            "the range must be finite, non-negative and non-empty",
+        );
+        violations.check(self.num_samples > 0, || ConfigError::Zero("num_samples"));
+        if let Some(progression) = &self.progression {
+            violations.add(progression.validate());
+        }
+        violations.into_result()
+    }
+
+    /// Takes the chords of consecutive events from a sampled progression instead of drawing them independently.
+    /// The chords of the progression must be registered in the [`DataParameters`] the sequence is generated from.
+    pub fn with_progression(mut self, progression: ProgressionDistribution) -> Self {
+        self.progression = Some(progression);
+        self
+    }
+
+    pub fn num_samples(&self) -> u64 {
+        self.num_samples
+    }
+
+    /// Checks the ranges of a deserialized sequence, that `data_parameters` has an oscillator with frequency and
+    /// that every chord the progression can produce is registered and among its possible chords.
+    pub fn check(&self, data_parameters: &DataParameters) -> Result<(), ConfigError> {
+        // The chords of an invalid progression cannot be listed.
+        self.validate()?;
+        let mut violations = Violations::default();
+        violations.check(data_parameters.has_pitched_oscillator(), || {
+            ConfigError::NoPitchedOscillator
+        });
+        if let Some(progression) = &self.progression {
+            for chord_type in progression.chord_types() {
+                violations.add(
+                    data_parameters
+                        .progression_chord_type(&chord_type)
+                        .map(|_| ()),
+                );
+            }
+        }
+        violations.into_result()
+    }
+
+    /// Samples the events of the clip with the given index. The length of each event replaces the number of samples
+    /// in `data_parameters`. Fails if [`SequenceParameters::check`] fails.
+    pub fn generate(
+        &self,
+        data_parameters: &DataParameters,
+        index: u64,
+    ) -> Result<SequenceEvents, ConfigError> {
+        self.check(data_parameters)?;
+        let seed = data_parameters.seed(index);
+        let mut rng = Pcg64Mcg::seed_from_u64(hash(seed));
+        let sample_rate = data_parameters.sample_rate() as f32;
+        let clip = DataPointParameters::new(data_parameters, seed);
+
+        let num_events = self.num_events_distribution.sample(&mut rng);
+        let mut progression = self
+            .progression
+            .as_ref()
+            .map(|progression| progression.sample(&mut rng, num_events).into_iter());
+        let mut onset = 0.;
+        let mut events = Vec::with_capacity(num_events);
+        for event_index in 0..num_events {
+            let onset_sample = (onset * sample_rate) as u64;
+            if onset_sample >= self.num_samples {
+                break;
+            }
+            let duration = self.duration_distribution.sample(&mut rng);
+            let end_sample = (((onset + duration) * sample_rate) as u64)
+                .clamp(onset_sample + 1, self.num_samples);
+            let mut parameters = DataPointParameters::with_progression_chord(
+                data_parameters,
+                hash(seed.wrapping_add(event_index as u64 + 1)),
+                progression.as_mut().and_then(|chords| chords.next()),
+            )?;
+            parameters.num_samples = end_sample - onset_sample;
+            parameters.oscillators = clip.oscillators.clone();
+            parameters.effects = Vec::new();
+            events.push(Event {
+                onset: onset_sample,
+                gain: 1.,
+                parameters,
+            });
+            onset += self.onset_interval_distribution.sample(&mut rng);
+        }
+
+        Ok(SequenceEvents {
+            clip,
+            events,
+            num_samples: self.num_samples,
+        })
+    }
+}
+
+/// A note or chord within a sequence.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Sample at which the event starts.
+    pub onset: u64,
+    /// Amplitude factor of the event, such as from the velocity of a MIDI note.
+    pub gain: f32,
+    /// Parameters of the event, where `num_samples` is its length.
+    pub parameters: DataPointParameters,
+}
+
+impl Event {
+    pub fn end(&self) -> u64 {
+        self.onset + self.parameters.num_samples
+    }
+}
+
+/// The sampled events of a clip, ready to be rendered.
+#[derive(Debug, Clone)]
+pub struct SequenceEvents {
+    /// Parameters of the clip, whose oscillators and effects are used for all events.
+    pub clip: DataPointParameters,
+    pub events: Vec<Event>,
+    pub num_samples: u64,
+}
+
+impl SequenceEvents {
+    pub fn generate(self) -> Sequence {
+        let sample_rate = self.clip.sample_rate;
+        let mut samples = vec![0.; self.num_samples as usize];
+        let mut num_active = vec![0.; self.num_samples as usize];
+        let fade_length = ((FADE_TIME * sample_rate as f32) as usize).max(1);
+
+        for event in self.events.iter() {
+            let event_samples = DataPoint::generate_from_oscillators(&event.parameters);
+            let length = event_samples.len();
+            let onset = event.onset as usize;
+            for (index, sample) in event_samples.into_iter().enumerate() {
+                let fade = (index.min(length - 1 - index) as f32 / fade_length as f32).min(1.);
+                samples[onset + index] += sample * fade * event.gain;
+                num_active[onset + index] += fade;
+            }
+        }
+
+        // Where events overlap, they are scaled down by the number of active events so the clip stays within the
+        // oscillator amplitudes. Counting the faded events fractionally keeps the scaling continuous at onsets and
+        // offsets, and events that do not overlap keep their level.
+        for (sample, num_active) in samples.iter_mut().zip(num_active) {
+            *sample /= num_active.max(1.);
+        }
+        DataPoint::apply_effects(&self.clip, &mut samples);
+
+        Sequence {
+            audio: Audio::from_samples(samples, sample_rate),
+            events: self,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Sequence {
+    pub audio: Audio,
+    pub events: SequenceEvents,
+}
+
+impl Sequence {
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    pub fn label(&self) -> SequenceLabel {
+        SequenceLabel {
+            sample_rate: self.audio.sample_rate,
+            num_samples: self.events.num_samples,
+            events: self
+                .events
+                .events
+                .iter()
+                .map(|event| EventLabel {
+                    onset: event.onset,
+                    end: event.end(),
+                    gain: event.gain,
+                    label: DataPointLabel::new(&event.parameters),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn frame_labels(&self, framing: Framing) -> FrameLabels {
+        let mut labels =
+            FrameLabels::empty(self.audio.sample_rate, self.audio.num_samples(), framing);
+        for event in self.events.events.iter() {
+            labels.add_event(event.onset as usize, &event.parameters);
+        }
+        labels
+    }
+
+    /// The notes of all events with velocities from their gains.
+    pub fn midi_file(&self) -> MidiFile {
+        let sample_rate = self.audio.sample_rate as f64;
+        MidiFile::new(
+            self.events
+                .events
+                .iter()
+                .flat_map(|event| {
+                    MidiFile::parameter_notes(
+                        &event.parameters,
+                        event.onset as f64 / sample_rate,
+                        event.gain,
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EventLabel {
+    /// Sample at which the event starts.
+    pub onset: u64,
+    /// Sample after the last sample of the event.
+    pub end: u64,
+    /// Amplitude factor of the event relative to the other events.
+    #[serde(default = "default_gain")]
+    pub gain: f32,
+    pub label: DataPointLabel,
+}
+
+fn default_gain() -> f32 {
+    1.
+}
+
+impl EventLabel {
+    pub fn onset_time(&self) -> f32 {
+        self.onset as f32 / self.label.sample_rate as f32
+    }
+
+    pub fn end_time(&self) -> f32 {
+        self.end as f32 / self.label.sample_rate as f32
+    }
+}
+
+/// Time-stamped events of a sequence, ordered by onset.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequenceLabel {
+    pub sample_rate: u32,
+    pub num_samples: u64,
+    pub events: Vec<EventLabel>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::SequenceParameters;
+    use crate::parameters::{
+        oscillators::OscillatorTypeDistribution, DataParameters, OctaveParameters, WaveForms,
+    };
+
+    const SAMPLE_RATE: u32 = 1000;
+
+    fn data_parameters() -> DataParameters {
+        data_parameters_with_chords(&[0])
+    }
+
+    fn data_parameters_with_chords(possible_chords: &[u32]) -> DataParameters {
+        DataParameters::new(
+            SAMPLE_RATE,
+            (100., 200.),
+            (0., 0.),
+            possible_chords,
+            OctaveParameters::new(0., 0., 20., 400.).unwrap(),
+            WaveForms::new(),
+            SAMPLE_RATE as u64,
+        )
+        .and_then(|parameters| {
+            parameters.with_oscillator(OscillatorTypeDistribution::Sine, 1., (0.5, 0.5))
+        })
+        .unwrap()
+    }
+
+    fn event_times(duration: f32, onset_interval: f32) -> Vec<(u64, u64)> {
+        SequenceParameters::new(
+            (5, 5),
+            (duration, duration),
+            (onset_interval, onset_interval),
+            SAMPLE_RATE as u64,
+        )
+        .unwrap()
+        .generate(&data_parameters(), 0)
+        .unwrap()
+        .events
+        .iter()
+        .map(|event| (event.onset, event.end()))
+        .collect()
+    }
+
+    #[test]
+    fn events_overlap_when_the_interval_is_shorter_than_the_duration() {
+        assert_eq!(
+            event_times(0.3, 0.2),
+            [(0, 300), (200, 500), (400, 700), (600, 900), (800, 1000)]
+        );
+    }
+
+    #[test]
+    fn events_leave_rests_and_stop_at_the_end_of_the_clip() {
+        assert_eq!(event_times(0.1, 0.4), [(0, 100), (400, 500), (800, 900)]);
+    }
+
+    #[test]
+    fn progression_chords_must_be_possible() {
+        use crate::{
+            config::ConfigError,
+            theory::{Mode, ProgressionDistribution, ProgressionModel},
+        };
+
+        // I and V of a major key are both major triads.
+        let sequence = SequenceParameters::new((4, 4), (0.1, 0.1), (0.2, 0.2), SAMPLE_RATE as u64)
+            .unwrap()
+            .with_progression(ProgressionDistribution::new(
+                [Mode::Ionian],
+                ProgressionModel::templates(vec![vec![0, 4]]).unwrap(),
+            ));
+        assert!(matches!(
+            sequence.generate(&data_parameters(), 0),
+            Err(ConfigError::UnavailableChord(intervals)) if intervals == [4, 7]
+        ));
+
+        let events = sequence
+            .generate(&data_parameters_with_chords(&[0, 2]), 0)
+            .unwrap()
+            .events;
+        assert_eq!(events.len(), 4);
+        assert!(events
+            .iter()
+            .all(|event| event.parameters.chord_name == "Major"));
+    }
+
+    #[test]
+    fn events_need_an_oscillator_with_frequency() {
+        use crate::config::ConfigError;
+
+        let data_parameters = DataParameters::new(
+            SAMPLE_RATE,
+            (100., 200.),
+            (0., 0.),
+            [0],
+            OctaveParameters::new(0., 0., 20., 400.).unwrap(),
+            WaveForms::new(),
+            SAMPLE_RATE as u64,
+        )
+        .and_then(|parameters| {
+            parameters.with_oscillator(OscillatorTypeDistribution::Noise, 1., (0.5, 0.5))
+        })
+        .unwrap();
+        let sequence =
+            SequenceParameters::new((1, 1), (0.1, 0.1), (0.1, 0.1), SAMPLE_RATE as u64).unwrap();
+        assert!(matches!(
+            sequence.generate(&data_parameters, 0),
+            Err(ConfigError::NoPitchedOscillator)
+        ));
+    }
+}
@@ -0,0 +1,490 @@
+use std::fmt::{Display, Formatter};
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    chord::ChordType,
+    chord_symbol,
+    config::{ConfigError, Violations},
+};
+
+/// Semitones of the degrees of the major scale above the tonic.
+const MAJOR_SCALE: [u32; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// The seven diatonic modes, in the order of the degree of the major scale they start on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    Ionian,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Aeolian,
+    Locrian,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 7] = [
+        Mode::Ionian,
+        Mode::Dorian,
+        Mode::Phrygian,
+        Mode::Lydian,
+        Mode::Mixolydian,
+        Mode::Aeolian,
+        Mode::Locrian,
+    ];
+
+    /// Semitones of the seven degrees of the mode above the tonic.
+    pub fn scale(self) -> [u32; 7] {
+        let rotation = self as usize;
+        let start = MAJOR_SCALE[rotation];
+        std::array::from_fn(|degree| (MAJOR_SCALE[(degree + rotation) % 7] + 12 - start) % 12)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Ionian => "major",
+            Mode::Dorian => "dorian",
+            Mode::Phrygian => "phrygian",
+            Mode::Lydian => "lydian",
+            Mode::Mixolydian => "mixolydian",
+            Mode::Aeolian => "minor",
+            Mode::Locrian => "locrian",
+        }
+    }
+
+    /// The mode chords are borrowed from: the parallel minor, or the parallel major for minor keys.
+    pub fn parallel(self) -> Mode {
+        if self == Mode::Aeolian {
+            Mode::Ionian
+        } else {
+            Mode::Aeolian
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Key {
+    /// Pitch class of the tonic, with C as 0.
+    pub tonic: u32,
+    pub mode: Mode,
+}
+
+impl Key {
+    pub fn new(tonic: u32, mode: Mode) -> Self {
+        Self {
+            tonic: tonic % 12,
+            mode,
+        }
+    }
+
+    /// Pitch class of a scale degree, counted from 0 for the tonic.
+    pub fn pitch_class(&self, degree: usize) -> u32 {
+        (self.tonic + self.mode.scale()[degree % 7]) % 12
+    }
+
+    /// Root pitch class and type of the chord stacked in thirds on a scale degree.
+    pub fn chord(&self, degree: usize, seventh: bool) -> (u32, ChordType) {
+        let root = self.pitch_class(degree);
+        let num_notes = if seventh { 4 } else { 3 };
+        let intervals: Vec<u32> = (1..num_notes)
+            .map(|third| (self.pitch_class(degree + 2 * third) + 12 - root) % 12)
+            .collect();
+        (root, ChordType::from_valid_intervals(intervals))
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            chord_symbol::pitch_class_name(self.tonic),
+            self.mode.name()
+        )
+    }
+}
+
+/// A chord of a progression and its function in the key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgressionChord {
+    pub key: Key,
+    /// Scale degree of the root, counted from 0 for the tonic.
+    pub degree: usize,
+    /// Pitch class of the root, with C as 0.
+    pub root: u32,
+    pub chord_type: ChordType,
+    /// Whether the chord is borrowed from the parallel mode.
+    pub borrowed: bool,
+}
+
+impl ProgressionChord {
+    /// Roman numeral of the chord such as `V7`, `ii` or `bVI`. Borrowed chords are marked with an accidental
+    /// if their root differs from the scale degree of the key.
+    pub fn roman_numeral(&self) -> String {
+        const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+        let accidental = match (self.root + 12 - self.key.pitch_class(self.degree)) % 12 {
+            0 => "",
+            1 => "#",
+            _ => "b",
+        };
+        let (third, fifth) = match self.chord_type.intervals() {
+            [third, fifth, ..] => (*third, *fifth),
+            _ => (4, 7),
+        };
+        let numeral = if third == 3 {
+            NUMERALS[self.degree].to_lowercase()
+        } else {
+            NUMERALS[self.degree].to_owned()
+        };
+        let quality = match (third, fifth, self.chord_type.intervals().get(2)) {
+            (3, 6, Some(10)) => "ø7",
+            (3, 6, Some(9)) => "°7",
+            (3, 6, _) => "°",
+            (4, 8, _) => "+",
+            (4, 7, Some(11)) => "maj7",
+            (_, _, Some(_)) => "7",
+            _ => "",
+        };
+        format!("{accidental}{numeral}{quality}")
+    }
+}
+
+/// How the scale degrees of a progression are chosen.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ProgressionModel {
+    /// First-order Markov chain over the seven scale degrees.
+    Markov {
+        start: Vec<f64>,
+        transitions: Vec<Vec<f64>>,
+    },
+    /// One of the given sequences of scale degrees, counted from 0 for the tonic, repeated as often as needed.
+    Templates(Vec<Vec<usize>>),
+}
+
+impl ProgressionModel {
+    pub fn markov(start: Vec<f64>, transitions: Vec<Vec<f64>>) -> Result<Self, ConfigError> {
+        let model = Self::Markov { start, transitions };
+        model.validate()?;
+        Ok(model)
+    }
+
+    pub fn templates(templates: Vec<Vec<usize>>) -> Result<Self, ConfigError> {
+        let model = Self::Templates(templates);
+        model.validate()?;
+        Ok(model)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        match self {
+            ProgressionModel::Markov { start, transitions } => {
+                violations.check(
+                    start.len() == 7
+                        && transitions.len() == 7
+                        && transitions.iter().all(|row| row.len() == 7),
+                    || ConfigError::Invalid {
+                        name: "Markov progression model",
+                        requirement: "it needs weights for the seven scale degrees",
+                    },
+                );
+                violations.check(
+                    start
+                        .iter()
+                        .chain(transitions.iter().flatten())
+                        .all(|&weight| weight.is_finite() && weight >= 0.),
+                    || ConfigError::Invalid {
+                        name: "Markov progression model",
+                        requirement: "weights must be finite and non-negative",
+                    },
+                );
+                violations.check(
+                    start.iter().any(|&weight| weight > 0.)
+                        && transitions
+                            .iter()
+                            .all(|row| row.iter().any(|&weight| weight > 0.)),
+                    || ConfigError::Invalid {
+                        name: "Markov progression model",
+                        requirement: "every degree needs a possible successor",
+                    },
+                );
+            }
+            ProgressionModel::Templates(templates) => {
+                violations.check(
+                    !templates.is_empty() && templates.iter().all(|template| !template.is_empty()),
+                    || ConfigError::Empty("progression templates"),
+                );
+                violations.check(templates.iter().flatten().all(|&degree| degree < 7), || {
+                    ConfigError::Invalid {
+                        name: "progression template",
+                        requirement: "scale degrees must be less than 7",
+                    }
+                });
+            }
+        }
+        violations.into_result()
+    }
+
+    /// Markov chain following common tendencies of functional harmony, such as V going to I and ii going to V.
+    pub fn functional() -> Self {
+        Self::Markov {
+            start: vec![0.7, 0.05, 0., 0.1, 0.05, 0.1, 0.],
+            transitions: vec![
+                vec![0.05, 0.15, 0.05, 0.3, 0.25, 0.15, 0.05],
+                vec![0.05, 0., 0.05, 0.1, 0.6, 0.1, 0.1],
+                vec![0.05, 0.05, 0., 0.3, 0.1, 0.5, 0.],
+                vec![0.25, 0.15, 0.05, 0., 0.4, 0.05, 0.1],
+                vec![0.6, 0., 0.05, 0.05, 0., 0.3, 0.],
+                vec![0.05, 0.35, 0.05, 0.35, 0.2, 0., 0.],
+                vec![0.8, 0., 0.1, 0., 0.1, 0., 0.],
+            ],
+        }
+    }
+
+    /// Common progressions such as I-V-vi-IV, ii-V-I and I-IV-V-I.
+    pub fn common_templates() -> Self {
+        Self::Templates(vec![
+            vec![0, 4, 5, 3],
+            vec![1, 4, 0],
+            vec![0, 3, 4, 0],
+            vec![5, 3, 0, 4],
+            vec![0, 5, 3, 4],
+            vec![0, 3, 0, 4],
+        ])
+    }
+
+    /// Scale degrees that can appear in a progression.
+    fn degrees(&self) -> Vec<usize> {
+        match self {
+            ProgressionModel::Markov { start, transitions } => (0..7)
+                .filter(|&degree| {
+                    start[degree] > 0. || transitions.iter().any(|row| row[degree] > 0.)
+                })
+                .collect(),
+            ProgressionModel::Templates(templates) => {
+                let mut degrees: Vec<usize> = templates.iter().flatten().copied().collect();
+                degrees.sort_unstable();
+                degrees.dedup();
+                degrees
+            }
+        }
+    }
+
+    fn sample_degrees<R: Rng + ?Sized>(&self, rng: &mut R, num_chords: usize) -> Vec<usize> {
+        let choose = |rng: &mut R, weights: &[f64]| {
+            let degrees: Vec<usize> = (0..7).collect();
+            *degrees
+                .choose_weighted(rng, |&degree| weights[degree])
+                .unwrap()
+        };
+        match self {
+            ProgressionModel::Markov { start, transitions } => {
+                let mut degrees = Vec::with_capacity(num_chords);
+                for index in 0..num_chords {
+                    let weights = match index {
+                        0 => start,
+                        _ => &transitions[degrees[index - 1]],
+                    };
+                    degrees.push(choose(rng, weights));
+                }
+                degrees
+            }
+            ProgressionModel::Templates(templates) => templates
+                .choose(rng)
+                .unwrap()
+                .iter()
+                .copied()
+                .cycle()
+                .take(num_chords)
+                .collect(),
+        }
+    }
+}
+
+/// Distribution over chord progressions in a randomly chosen key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProgressionDistribution {
+    tonics: Vec<u32>,
+    modes: Vec<Mode>,
+    model: ProgressionModel,
+    seventh_probability: f64,
+    borrowed_probability: f64,
+}
+
+impl ProgressionDistribution {
+    /// Progressions of diatonic triads in any of the twelve tonics and one of `modes`.
+    pub fn new<A>(modes: A, model: ProgressionModel) -> Self
+    where
+        A: Into<Vec<Mode>>,
+    {
+        let modes = modes.into();
+        assert!(!modes.is_empty(), "No modes provided.");
+        Self {
+            tonics: (0..12).collect(),
+            modes,
+            model,
+            seventh_probability: 0.,
+            borrowed_probability: 0.,
+        }
+    }
+
+    /// Restricts the tonics of the keys to the given pitch classes.
+    pub fn with_tonics<A>(mut self, tonics: A) -> Self
+    where
+        A: Into<Vec<u32>>,
+    {
+        self.tonics = tonics.into();
+        assert!(
+            !self.tonics.is_empty() && self.tonics.iter().all(|&tonic| tonic < 12),
+            "Tonics must be pitch classes. Tonics: {:?}",
+            self.tonics
+        );
+        self
+    }
+
+    /// Each chord is a seventh chord with the given probability.
+    pub fn with_sevenths(mut self, probability: f64) -> Self {
+        assert!(
+            (0. ..=1.).contains(&probability),
+            "Probability must be within [0;1]. Value: {probability}"
+        );
+        self.seventh_probability = probability;
+        self
+    }
+
+    /// Each chord is taken from the parallel mode with the given probability, such as bVI or iv in a major key.
+    pub fn with_borrowed_chords(mut self, probability: f64) -> Self {
+        assert!(
+            (0. ..=1.).contains(&probability),
+            "Probability must be within [0;1]. Value: {probability}"
+        );
+        self.borrowed_probability = probability;
+        self
+    }
+
+    /// Checks what the constructors check, for progressions that were deserialized.
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        let mut violations = Violations::default();
+        violations.check(!self.modes.is_empty(), || ConfigError::Empty("modes"));
+        violations.check(
+            !self.tonics.is_empty() && self.tonics.iter().all(|&tonic| tonic < 12),
+            || ConfigError::Invalid {
+                name: "progression tonics",
+                requirement:
+                    "there must be at least one and every tonic must be a pitch class below 12",
+            },
+        );
+        violations.check_probability("seventh_probability", self.seventh_probability, true);
+        violations.check_probability("borrowed_probability", self.borrowed_probability, true);
+        violations.add(self.model.validate());
+        violations.into_result()
+    }
+
+    /// Every chord type a sampled progression can contain, so they can be checked against the chord registry
+    /// before generating.
+    pub fn chord_types(&self) -> Vec<ChordType> {
+        let mut modes = self.modes.clone();
+        if self.borrowed_probability > 0. {
+            modes.extend(self.modes.iter().map(|mode| mode.parallel()));
+        }
+        let sevenths: Vec<bool> = [
+            (false, self.seventh_probability < 1.),
+            (true, self.seventh_probability > 0.),
+        ]
+        .into_iter()
+        .filter_map(|(seventh, possible)| possible.then_some(seventh))
+        .collect();
+        let mut chord_types: Vec<ChordType> = Vec::new();
+        for mode in modes {
+            for degree in self.model.degrees() {
+                for &seventh in sevenths.iter() {
+                    let (_, chord_type) = Key::new(0, mode).chord(degree, seventh);
+                    if !chord_types.contains(&chord_type) {
+                        chord_types.push(chord_type);
+                    }
+                }
+            }
+        }
+        chord_types
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R, num_chords: usize) -> Vec<ProgressionChord> {
+        let key = Key::new(
+            *self.tonics.choose(rng).unwrap(),
+            *self.modes.choose(rng).unwrap(),
+        );
+        let parallel_key = Key::new(key.tonic, key.mode.parallel());
+        self.model
+            .sample_degrees(rng, num_chords)
+            .into_iter()
+            .map(|degree| {
+                let seventh = rng.gen_bool(self.seventh_probability);
+                let diatonic = key.chord(degree, seventh);
+                let (root, chord_type) = if rng.gen_bool(self.borrowed_probability) {
+                    parallel_key.chord(degree, seventh)
+                } else {
+                    diatonic.clone()
+                };
+                ProgressionChord {
+                    key,
+                    degree,
+                    borrowed: (root, &chord_type) != (diatonic.0, &diatonic.1),
+                    root,
+                    chord_type,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Key, Mode, ProgressionChord, ProgressionDistribution, ProgressionModel};
+
+    #[test]
+    fn roman_numerals() {
+        let c_major = Key::new(0, Mode::Ionian);
+        let c_minor = Key::new(0, Mode::Aeolian);
+        let chord = |key: Key, degree, seventh, borrowed_from: Key| {
+            let (root, chord_type) = borrowed_from.chord(degree, seventh);
+            ProgressionChord {
+                key,
+                degree,
+                root,
+                chord_type,
+                borrowed: key != borrowed_from,
+            }
+            .roman_numeral()
+        };
+        assert_eq!(chord(c_major, 0, false, c_major), "I");
+        assert_eq!(chord(c_major, 1, true, c_major), "ii7");
+        assert_eq!(chord(c_major, 4, true, c_major), "V7");
+        assert_eq!(chord(c_major, 6, false, c_major), "vii°");
+        assert_eq!(chord(c_major, 6, true, c_major), "viiø7");
+        assert_eq!(chord(c_major, 0, true, c_major), "Imaj7");
+        assert_eq!(chord(c_major, 5, false, c_minor), "bVI");
+        assert_eq!(chord(c_major, 3, false, c_minor), "iv");
+        assert_eq!(chord(c_minor, 2, false, c_minor), "III");
+        assert_eq!(Key::new(2, Mode::Dorian).to_string(), "D dorian");
+    }
+
+    #[test]
+    fn deserialized_models_are_checked() {
+        assert!(ProgressionModel::functional().validate().is_ok());
+        assert!(ProgressionModel::common_templates().validate().is_ok());
+
+        let models = [
+            r#"{"Markov": {"start": [1, 0, 0], "transitions": []}}"#,
+            r#"{"Markov": {"start": [1, 0, 0, 0, 0, 0, -1], "transitions": [[1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0], [1, 0, 0, 0, 0, 0, 0]]}}"#,
+            r#"{"Templates": [[0, 7]]}"#,
+        ];
+        for model in models {
+            let model: ProgressionModel = serde_json::from_str(model).unwrap();
+            assert!(model.validate().is_err(), "{model:?}");
+            assert!(ProgressionDistribution::new([Mode::Ionian], model)
+                .validate()
+                .is_err());
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::f32::consts::SQRT_2;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ConfigError, scala::ScalaTuning};
+
+/// Frequency ratios of the intervals within an octave in 5-limit just intonation, indexed by semitones.
+///
+/// The tritone has no simple 5-limit ratio, so it is tuned to exactly half an octave.
+const JUST_RATIOS: [f32; 12] = [
+    1.,
+    16. / 15.,
+    9. / 8.,
+    6. / 5.,
+    5. / 4.,
+    4. / 3.,
+    SQRT_2,
+    3. / 2.,
+    8. / 5.,
+    5. / 3.,
+    9. / 5.,
+    15. / 8.,
+];
+
+/// Frequency ratios of the intervals within an octave in Pythagorean tuning, built from stacked pure fifths.
+const PYTHAGOREAN_RATIOS: [f32; 12] = [
+    1.,
+    256. / 243.,
+    9. / 8.,
+    32. / 27.,
+    81. / 64.,
+    4. / 3.,
+    729. / 512.,
+    3. / 2.,
+    128. / 81.,
+    27. / 16.,
+    16. / 9.,
+    243. / 128.,
+];
+
+/// How notes and the intervals of chords are tuned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Tuning {
+    /// 12-tone equal temperament, matching the note numbers in labels.
+    EqualTemperament,
+    /// 5-limit just intonation.
+    JustIntonation,
+    Pythagorean,
+    /// Offsets of the twelve semitones of an octave from the root in cents.
+    Custom(CentOffsets),
+    /// A Scala scale and keyboard mapping. Chord intervals are counted in scale degrees, which are semitones
+    /// for scales with twelve notes, and sampled notes are snapped to the mapped keys.
+    Scala(ScalaTuning),
+}
+
+impl Tuning {
+    /// A tuning from the offsets of the twelve semitones of an octave from the root in cents.
+    pub fn custom<A>(cents: A) -> Result<Self, ConfigError>
+    where
+        A: Into<Vec<f32>>,
+    {
+        Ok(Self::Custom(cents.into().try_into()?))
+    }
+
+    /// Frequency ratio of an interval of the given number of semitones, which is negative for descending intervals.
+    pub fn ratio(&self, semitones: i32) -> f32 {
+        let step = semitones.rem_euclid(12) as usize;
+        // Ratio within the octave moved to the octave of the interval.
+        let octave_ratio = |ratio: f32| ratio * (semitones.div_euclid(12) as f32).exp2();
+        match self {
+            Tuning::EqualTemperament => octave_ratio((step as f32 / 12.).exp2()),
+            Tuning::JustIntonation => octave_ratio(JUST_RATIOS[step]),
+            Tuning::Pythagorean => octave_ratio(PYTHAGOREAN_RATIOS[step]),
+            Tuning::Custom(cents) => octave_ratio((cents.0[step] / 1200.).exp2()),
+            Tuning::Scala(scala) => scala.scale().ratio(semitones),
+        }
+    }
+
+    /// Checks a deserialized tuning. Custom offsets are checked when they are deserialized.
+    pub(crate) fn validate(&self) -> Result<(), ConfigError> {
+        match self {
+            Tuning::Scala(scala) => scala.validate().map_err(ConfigError::Scala),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fractional note number of a frequency with A4 tuned to `a4_frequency`.
+    ///
+    /// Scala tunings use their keyboard mapping, shifted by the difference between `a4_frequency` and
+    /// [`crate::A4_FREQUENCY`]. All other tunings use 12-tone equal temperament.
+    pub fn frequency_to_note_number(&self, frequency: f32, a4_frequency: f32) -> f32 {
+        match self {
+            Tuning::Scala(scala) => {
+                scala.frequency_to_note_number(frequency * crate::A4_FREQUENCY / a4_frequency)
+            }
+            _ => crate::frequency_to_note_number_with_reference(frequency, a4_frequency),
+        }
+    }
+
+    /// Pitch class of a note number, with C as 0. Scala tunings whose scale does not have twelve degrees have no
+    /// pitch classes, since their keys do not correspond to the twelve note names.
+    pub fn pitch_class(&self, note_number: u32) -> Option<u32> {
+        match self {
+            Tuning::Scala(scala) if scala.scale().len() != 12 => None,
+            _ => Some(note_number % 12),
+        }
+    }
+
+    /// Inverse of [`Tuning::frequency_to_note_number`].
+    pub fn note_number_to_frequency(&self, note_number: f32, a4_frequency: f32) -> f32 {
+        match self {
+            Tuning::Scala(scala) => {
+                scala.note_number_to_frequency(note_number) * a4_frequency / crate::A4_FREQUENCY
+            }
+            _ => crate::note_number_to_frequency_with_reference(note_number, a4_frequency),
+        }
+    }
+}
+
+/// Twelve finite offsets in cents, checked when they are created or deserialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<f32>", into = "Vec<f32>")]
+pub struct CentOffsets(Vec<f32>);
+
+impl CentOffsets {
+    pub fn cents(&self) -> &[f32] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<f32>> for CentOffsets {
+    type Error = ConfigError;
+
+    fn try_from(cents: Vec<f32>) -> Result<Self, Self::Error> {
+        if cents.len() == 12 && cents.iter().all(|cent| cent.is_finite()) {
+            Ok(Self(cents))
+        } else {
+            Err(ConfigError::InvalidTuning(cents))
+        }
+    }
+}
+
+impl From<CentOffsets> for Vec<f32> {
+    fn from(offsets: CentOffsets) -> Self {
+        offsets.0
+    }
+}
+
+impl Default for Tuning {
+    /// Just intonation, which chords were always tuned in before the tuning became configurable.
+    fn default() -> Self {
+        Tuning::JustIntonation
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Tuning;
+
+    #[test]
+    fn custom_tunings_need_twelve_offsets() {
+        let cents: Vec<f32> = (0..12).map(|step| step as f32 * 100.).collect();
+        let tuning = Tuning::custom(cents.clone()).unwrap();
+        assert!((tuning.ratio(19) - (1900f32 / 1200.).exp2()).abs() < 1e-5);
+        assert_eq!(
+            serde_json::from_str::<Tuning>(&serde_json::to_string(&tuning).unwrap()).unwrap(),
+            tuning
+        );
+
+        assert!(Tuning::custom(&cents[..11]).is_err());
+        assert!(serde_json::from_str::<Tuning>(r#"{"Custom": [0, 100]}"#).is_err());
+    }
+
+    #[test]
+    fn only_twelve_note_scales_have_pitch_classes() {
+        use crate::scala::{KeyboardMapping, ScalaTuning, Scale};
+
+        let scala = |scale: &str| {
+            let scale = Scale::parse(scale).unwrap();
+            Tuning::Scala(ScalaTuning::new(scale, KeyboardMapping::linear(62, 69, 440.)).unwrap())
+        };
+        let chromatic: String = (1..=12).map(|step| format!("{}.0\n", step * 100)).collect();
+        let chromatic = scala(&format!("Chromatic\n12\n{chromatic}"));
+        let pentatonic = scala("Pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n");
+
+        assert_eq!(Tuning::JustIntonation.pitch_class(64), Some(4));
+        assert_eq!(chromatic.pitch_class(64), Some(4));
+        assert_eq!(pentatonic.pitch_class(64), None);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+};
+
+use crate::Audio;
+
+/// Length of the windows used to find a stable region in seconds.
+const WINDOW_LENGTH: f32 = 0.05;
+/// Windows quieter than this fraction of the loudest window are not considered stable.
+const MIN_RELATIVE_RMS: f32 = 0.5;
+/// Maximum pitch difference between neighbouring windows in a stable region in cents.
+const MAX_PITCH_DEVIATION: f32 = 10.;
+/// Threshold for the cumulative mean normalized difference function used in pitch estimation.
+const YIN_THRESHOLD: f32 = 0.15;
+const MIN_FREQUENCY: f32 = 30.;
+const MAX_FREQUENCY: f32 = 4000.;
+
+#[derive(Debug)]
+pub enum ExtractionError {
+    TooShort(usize),
+    /// The sample rate is too low for the analysis windows to hold a single sample.
+    SampleRateTooLow(u32),
+    /// Zero periods were requested.
+    NoPeriods,
+    NoPitch,
+    NoZeroCrossing,
+}
+
+impl Display for ExtractionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooShort(num_samples) => write!(
+                f,
+                "Recording of {num_samples} samples is too short to extract a wave form from."
+            ),
+            Self::SampleRateTooLow(sample_rate) => write!(
+                f,
+                "Sample rate {sample_rate} is too low to extract a wave form from."
+            ),
+            Self::NoPeriods => write!(f, "Must extract at least one period."),
+            Self::NoPitch => write!(f, "Could not find a region with a stable pitch."),
+            Self::NoZeroCrossing => write!(
+                f,
+                "Could not find an upwards zero crossing in the stable region."
+            ),
+        }
+    }
+}
+
+impl Error for ExtractionError {}
+
+/// Estimates the fundamental frequency of a signal using the YIN algorithm.
+///
+/// Returns `None` if the signal has no clear periodicity between 30 Hz and 4 kHz.
+pub fn estimate_frequency(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    let min_lag = (sample_rate as f32 / MAX_FREQUENCY) as usize;
+    let max_lag = ((sample_rate as f32 / MIN_FREQUENCY) as usize).min(samples.len() / 2);
+    if max_lag <= min_lag + 2 {
+        return None;
+    }
+    let window = samples.len() - max_lag;
+
+    let difference: Vec<f32> = (0..=max_lag)
+        .map(|lag| {
+            samples[..window]
+                .iter()
+                .zip(&samples[lag..lag + window])
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum()
+        })
+        .collect();
+
+    let mut running_sum = 0.;
+    let normalized: Vec<f32> = difference
+        .iter()
+        .enumerate()
+        .map(|(lag, &value)| {
+            if lag == 0 {
+                1.
+            } else {
+                running_sum += value;
+                if running_sum > 0. {
+                    value * lag as f32 / running_sum
+                } else {
+                    1.
+                }
+            }
+        })
+        .collect();
+
+    let mut lag = (min_lag.max(1)..max_lag).find(|&lag| normalized[lag] < YIN_THRESHOLD)?;
+    while lag + 1 < max_lag && normalized[lag + 1] < normalized[lag] {
+        lag += 1;
+    }
+
+    // Parabolic interpolation around the minimum.
+    let (left, center, right) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+    let denominator = left - 2. * center + right;
+    let offset = if denominator.abs() > f32::EPSILON {
+        0.5 * (left - right) / denominator
+    } else {
+        0.
+    };
+    Some(sample_rate as f32 / (lag as f32 + offset))
+}
+
+/// Finds the longest run of loud windows with a steady pitch and returns its sample range and mean frequency.
+pub fn stable_region(audio: &Audio) -> Result<(usize, usize, f32), ExtractionError> {
+    let window_length = (WINDOW_LENGTH * audio.sample_rate as f32) as usize;
+    if window_length == 0 {
+        return Err(ExtractionError::SampleRateTooLow(audio.sample_rate));
+    }
+    let windows: Vec<&[f32]> = audio.samples.chunks_exact(window_length).collect();
+    if windows.len() < 2 {
+        return Err(ExtractionError::TooShort(audio.num_samples()));
+    }
+    let rms: Vec<f32> = windows
+        .iter()
+        .map(|window| (window.iter().map(|x| x * x).sum::<f32>() / window.len() as f32).sqrt())
+        .collect();
+    let max_rms = rms.iter().cloned().fold(0., f32::max);
+
+    // Neighbouring windows overlap so periods spanning a window boundary are still detected.
+    let pitches: Vec<Option<f32>> = (0..windows.len())
+        .map(|index| {
+            if rms[index] < max_rms * MIN_RELATIVE_RMS || index + 1 == windows.len() {
+                None
+            } else {
+                let start = index * window_length;
+                estimate_frequency(
+                    &audio.samples[start..start + 2 * window_length],
+                    audio.sample_rate,
+                )
+            }
+        })
+        .collect();
+
+    let mut best: Option<(usize, usize)> = None;
+    let mut run_start = 0;
+    for index in 0..pitches.len() {
+        let continues = match (
+            index.checked_sub(1).and_then(|i| pitches[i]),
+            pitches[index],
+        ) {
+            (Some(previous), Some(current)) => {
+                crate::cent_diff(previous, current).abs() < MAX_PITCH_DEVIATION
+            }
+            _ => false,
+        };
+        if !continues {
+            run_start = index;
+        }
+        if pitches[index].is_some()
+            && best.is_none_or(|(start, end)| index + 1 - run_start > end - start)
+        {
+            best = Some((run_start, index + 1));
+        }
+    }
+
+    let (start, end) = best.ok_or(ExtractionError::NoPitch)?;
+    let frequency = pitches[start..end].iter().flatten().sum::<f32>() / (end - start) as f32;
+    Ok((start * window_length, end * window_length, frequency))
+}
+
+/// Linearly interpolated value of the samples at a fractional position.
+fn interpolate(samples: &[f32], position: f32) -> f32 {
+    let index = position.floor() as usize;
+    let fraction = position - index as f32;
+    let next = samples.get(index + 1).copied().unwrap_or(samples[index]);
+    samples[index] * (1. - fraction) + next * fraction
+}
+
+/// Extracts `num_periods` periods from the middle of the most stable region of a recorded note.
+///
+/// The extracted wave form starts at an upwards zero crossing, has its DC offset removed and is normalized to a peak of 1.
+/// It is resampled so that the periods fit exactly in the returned samples.
+pub fn extract_wave_form(audio: &Audio, num_periods: usize) -> Result<Vec<f32>, ExtractionError> {
+    if num_periods == 0 {
+        return Err(ExtractionError::NoPeriods);
+    }
+    let (start, end, frequency) = stable_region(audio)?;
+    let period = audio.sample_rate as f32 / frequency;
+    let length = period * num_periods as f32;
+    let samples = &audio.samples;
+
+    let middle = (start + end) / 2;
+    let crossing = (middle..end)
+        .find(|&index| {
+            index + 1 < samples.len()
+                && samples[index] <= 0.
+                && samples[index + 1] > 0.
+                && ((index + 1) as f32 + length) < samples.len() as f32
+        })
+        .ok_or(ExtractionError::NoZeroCrossing)?;
+    let crossing =
+        crossing as f32 + samples[crossing] / (samples[crossing] - samples[crossing + 1]);
+
+    let num_samples = length.round() as usize;
+    let mut wave_form: Vec<f32> = (0..num_samples)
+        .map(|i| interpolate(samples, crossing + i as f32 * length / num_samples as f32))
+        .collect();
+
+    let mean = wave_form.iter().sum::<f32>() / num_samples as f32;
+    wave_form.iter_mut().for_each(|sample| *sample -= mean);
+    let peak = wave_form
+        .iter()
+        .map(|sample| sample.abs())
+        .fold(0., f32::max);
+    if peak > 0. {
+        wave_form.iter_mut().for_each(|sample| *sample /= peak);
+    }
+    Ok(wave_form)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::TAU;
+
+    use crate::Audio;
+
+    #[test]
+    fn extract_from_decaying_note() {
+        let sample_rate = 44100;
+        let frequency = 220.;
+        let samples = (0..sample_rate)
+            .map(|i| {
+                let time = i as f32 / sample_rate as f32;
+                let phase = frequency * time;
+                let envelope = (time * 20.).min(1.) * (-time).exp();
+                ((phase * TAU).sin() + 0.5 * (phase * 2. * TAU).sin()) * envelope * 0.5
+            })
+            .collect();
+        let audio = Audio::from_samples(samples, sample_rate);
+
+        let estimate =
+            super::estimate_frequency(&audio.samples[10000..14000], sample_rate).unwrap();
+        assert!(
+            crate::cent_diff(frequency, estimate).abs() < 5.,
+            "Estimate: {estimate}"
+        );
+
+        let wave_form = super::extract_wave_form(&audio, 2).unwrap();
+        let expected_length = 2. * sample_rate as f32 / frequency;
+        assert!((wave_form.len() as f32 - expected_length).abs() <= 1.);
+        assert!(wave_form[0].abs() < 0.05);
+        let peak = wave_form.iter().map(|x| x.abs()).fold(0., f32::max);
+        assert!((peak - 1.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn invalid_requests_are_errors() {
+        use super::ExtractionError;
+
+        let audio = Audio::from_samples(vec![0.; 100], 10);
+        assert!(matches!(
+            super::stable_region(&audio),
+            Err(ExtractionError::SampleRateTooLow(10))
+        ));
+        assert!(matches!(
+            super::extract_wave_form(&audio, 0),
+            Err(ExtractionError::NoPeriods)
+        ));
+    }
+}